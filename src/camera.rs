@@ -0,0 +1,111 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Cameras for `Display::set_camera()`.
+
+use adi_gpu_base::*;
+
+use math::{cross, dot, normalize, sub};
+
+/// Where the camera is and which way it faces.  The camera looks down +Z of
+/// its own space, with +Y towards the bottom of the screen.
+#[derive(Copy, Clone)]
+pub enum Camera {
+	/// Move to `xyz`, then rotate by `rotate_xyz` (Euler angles), like
+	/// `Display::camera()`.
+	Euler {
+		xyz: Vec3,
+		rotate_xyz: Vec3,
+	},
+	/// Stand at `eye` facing `target`, with `up` towards the top of the
+	/// screen.
+	LookAt {
+		eye: Vec3,
+		target: Vec3,
+		up: Vec3,
+	},
+	/// Stand at `eye`, turned by a unit quaternion `orientation` (x, y, z,
+	/// w).  Doesn't gimbal lock.
+	Quaternion {
+		eye: Vec3,
+		orientation: [f32; 4],
+	},
+	/// A view matrix, from world space to camera space.  It shouldn't
+	/// scale or skew, so that the camera position can be found for
+	/// sorting.
+	View(Transform),
+}
+
+impl Camera {
+	// Get the view matrix.
+	pub(crate) fn transform(&self) -> Transform {
+		match *self {
+			Camera::Euler { xyz, rotate_xyz } => Transform::IDENTITY
+				.t(vec3!()-xyz) // Move camera - TODO: negation operator?
+				.r(vec3!()-rotate_xyz), // Rotate camera - TODO: negation operator?
+			Camera::LookAt { eye, target, up } => {
+				let eye = [eye.x, eye.y, eye.z];
+				let forward = normalize(sub([target.x, target.y,
+					target.z], eye));
+				let right = normalize(cross(forward,
+					[up.x, up.y, up.z]));
+				let down = cross(forward, right);
+
+				view(eye, right, down, forward)
+			}
+			Camera::Quaternion { eye, orientation } => {
+				let (x, y, z, w) = (orientation[0], orientation[1],
+					orientation[2], orientation[3]);
+
+				// Columns of the rotation matrix are where the
+				// camera's axes point in the world.
+				let right = [1.0 - 2.0 * (y * y + z * z),
+					2.0 * (x * y + w * z), 2.0 * (x * z - w * y)];
+				let down = [2.0 * (x * y - w * z),
+					1.0 - 2.0 * (x * x + z * z),
+					2.0 * (y * z + w * x)];
+				let forward = [2.0 * (x * z + w * y),
+					2.0 * (y * z - w * x),
+					1.0 - 2.0 * (x * x + y * y)];
+
+				view([eye.x, eye.y, eye.z], right, down, forward)
+			}
+			Camera::View(transform) => transform,
+		}
+	}
+
+	// Get the position of the camera in the world.
+	pub(crate) fn eye(&self) -> Vec3 {
+		match *self {
+			Camera::Euler { xyz, .. } => xyz,
+			Camera::LookAt { eye, .. } => eye,
+			Camera::Quaternion { eye, .. } => eye,
+			Camera::View(transform) => {
+				// eye = -(transpose of rotation) * translation
+				let t = transform.0 * vec4!(0.0, 0.0, 0.0, 1.0);
+				let x = transform.0 * vec4!(1.0, 0.0, 0.0, 0.0);
+				let y = transform.0 * vec4!(0.0, 1.0, 0.0, 0.0);
+				let z = transform.0 * vec4!(0.0, 0.0, 1.0, 0.0);
+
+				vec3!(
+					-(x.x * t.x + x.y * t.y + x.z * t.z),
+					-(y.x * t.x + y.y * t.y + y.z * t.z),
+					-(z.x * t.x + z.y * t.y + z.z * t.z)
+				)
+			}
+		}
+	}
+}
+
+// Make a view matrix from the camera's position and axes in the world.
+// Matrices are written column by column.
+fn view(eye: [f32; 3], x: [f32; 3], y: [f32; 3], z: [f32; 3]) -> Transform {
+	Transform(mat4!(
+		x[0], y[0], z[0], 0.0,
+		x[1], y[1], z[1], 0.0,
+		x[2], y[2], z[2], 0.0,
+		-dot(x, eye), -dot(y, eye), -dot(z, eye), 1.0
+	))
+}
@@ -0,0 +1,56 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Custom shader styles, from `Display::custom_style()`.
+
+use std::borrow::Cow;
+
+use adi_gpu_base::Transform;
+use asi_opengl::UniformData;
+
+/// A handle to a style made from your own GLSL.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CustomStyle(pub(crate) usize); // See `handle`.
+
+/// A value for a uniform in a custom style's shaders.
+#[derive(Copy, Clone)]
+pub enum Uniform {
+	/// `int`
+	Int(i32),
+	/// `float`
+	Float(f32),
+	/// `vec2`
+	Vec2([f32; 2]),
+	/// `vec4`
+	Vec4([f32; 4]),
+	/// `mat4`
+	Mat4(Transform),
+}
+
+impl Uniform {
+	// Set a uniform to this value.
+	pub(crate) fn set(&self, uniform: &UniformData) {
+		match *self {
+			Uniform::Int(v) => uniform.set_int1(v),
+			Uniform::Float(v) => uniform.set_vec1(v),
+			Uniform::Vec2(ref v) => uniform.set_vec2(v),
+			Uniform::Vec4(ref v) => uniform.set_vec4(v),
+			Uniform::Mat4(v) => uniform.set_mat4(v.into()),
+		}
+	}
+}
+
+// Get a uniform or attribute name ending in the nul terminator OpenGL needs,
+// adding it if it's missing.
+pub(crate) fn nul_terminated<'a>(name: &'a [u8]) -> Cow<'a, [u8]> {
+	if name.last() == Some(&0) {
+		Cow::Borrowed(name)
+	} else {
+		let mut name = name.to_vec();
+
+		name.push(0);
+		Cow::Owned(name)
+	}
+}
@@ -0,0 +1,1018 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! The parts of a `Display` that don't depend on how it draws: the shapes,
+//! models, textures, camera, viewports, fog and lights.  Backends only upload
+//! buffers and textures, and draw one shape at a time.
+
+use adi_gpu_base as base;
+use adi_gpu_base::*;
+use asi_opengl::Topology;
+
+use camera::Camera;
+use custom::Uniform;
+use fog::{self, Fog};
+use handle;
+use indexed::{self, Indices, Primitive};
+use light::{Light, MAX_LIGHTS};
+use material::{Factors, Material};
+use projection::{self, Projection};
+use resources::Resources;
+use shapes::Shapes;
+use vector::Path;
+use viewport::Viewport;
+use Error;
+use Gl;
+use {
+	STYLE_GRADIENT, STYLE_TEXTURE, STYLE_FADED, STYLE_TINTED, STYLE_SOLID,
+	STYLE_COMPLEX, STYLE_VG, STYLE_LIT_SOLID, STYLE_LIT_TEXTURE,
+	STYLE_LIT_TINTED, STYLE_PBR,
+};
+
+/// How a `Display` draws: `Gl` into a window, or `Software` into memory.
+pub trait Backend: Sized {
+	/// A buffer of vertex attributes, 4 floats per vertex.
+	type Buffer: Clone;
+	/// A texture.
+	type Texture: Clone;
+	/// Where a custom style's uniform is.
+	type Location;
+
+	/// Upload a buffer of vertex attributes.
+	fn buffer(&mut self, data: &[f32]) -> Self::Buffer;
+
+	/// Upload a `wh` texture.
+	fn texture(&mut self, wh: (u16, u16), pixels: &[u32]) -> Self::Texture;
+
+	/// Replace the pixels of a texture.
+	fn set_texture(&mut self, texture: &Self::Texture, wh: (u16, u16),
+		pixels: &[u32]);
+
+	/// Check that PBR shapes can use textures besides the base color,
+	/// returning tangents for the model if there's a normal map.
+	fn material_maps(&mut self, vertices: &Self::Buffer,
+		texcoords: &Self::Buffer, normals: &Self::Buffer,
+		topology: Topology, fans: &[(u32, u32)], normal_map: bool)
+		-> Result<Option<Self::Buffer>, Error>;
+
+	/// Get the width and height of what's drawn into.
+	fn wh(&self) -> (u16, u16);
+
+	/// Change the width and height of what's drawn into.
+	fn resize(&mut self, wh: (u16, u16));
+
+	/// Start a frame, cleared to `color`.  Returns input instead if there
+	/// is some, and then nothing is drawn.
+	fn begin(&mut self, color: (f32, f32, f32), reversed_z: bool)
+		-> Option<Input>;
+
+	/// Set the uniforms that are the same for every shape until the next
+	/// pass.
+	fn pass(&mut self, pass: &Pass);
+
+	/// Draw a shape.
+	fn draw(&mut self, shape: &ShapeData<Self>);
+
+	/// Finish the frame.
+	fn end(&mut self);
+
+	/// Called after `begin()`, before the opaque and alpha shapes are
+	/// drawn.
+	fn before_world(_display: &mut Display<Self>) {}
+
+	/// Called after the opaque and alpha shapes are drawn, before the GUI.
+	fn after_world(_display: &mut Display<Self>) {}
+}
+
+/// Uniforms that are the same for every shape in a pass.
+#[derive(Clone)]
+pub struct Pass {
+	/// The camera and projection, or `None` for GUI shapes.
+	pub camera: Option<Transform>,
+	/// Squeezes clip space into the viewport, after `camera` and after the
+	/// fog distance is found.
+	pub squeeze: Transform,
+	/// Only draw inside of this rectangle: left, top, width and height in
+	/// pixels.
+	pub viewport: (u16, u16, u16, u16),
+	/// Where the camera is, for the lit styles.
+	pub eye: Vec3,
+	/// The `fog` and `range` uniforms.
+	pub fog: ([f32; 4], [f32; 4]),
+	/// The `sky` uniforms, or `None` for fog in its own color.
+	pub sky: Option<fog::Sky>,
+	pub lights: Vec<Light>,
+	pub depth_test: bool,
+	/// Near is at depth 1, and the depth test is GREATER instead of LESS.
+	pub reversed_z: bool,
+}
+
+/// Some of the opaque and alpha shapes, by handle.
+#[derive(Clone)]
+pub struct Selection {
+	pub opaque: Vec<u32>,
+	pub alpha: Vec<u32>,
+}
+
+pub struct ShapeData<B: Backend> {
+	pub style: usize,
+	// Vertex attributes, in the order of `STYLE_ATTRIBUTES`' indices.
+	pub buffers: Vec<B::Buffer>,
+	pub uniforms: Vec<(B::Location, Uniform)>, // For custom styles.
+	pub has_fog: bool,
+	pub alpha: Option<f32>,
+	pub color: Option<[f32; 4]>,
+	pub material: Option<MaterialData<B>>, // For the PBR style.
+	pub transform: Transform, // Transformation matrix.
+	pub layer: i32, // Stacking order of GUI shapes.
+	pub cast_shadows: bool,
+	pub receive_shadows: bool,
+	pub texture: Option<B::Texture>,
+	pub vertex_buffer: B::Buffer,
+	pub topology: Topology,
+	pub fans: Vec<(u32, u32)>,
+}
+
+impl<B: Backend> ::adi_gpu_base::Point for ShapeData<B> {
+	fn point(&self) -> Vec3 {
+		// Position vector at origin * object transform.
+		(self.transform.0 * vec4!(0f32, 0f32, 0f32, 1f32)).xyz()
+	}
+}
+
+pub struct MaterialData<B: Backend> {
+	pub factors: Factors,
+	// Metallic-roughness, normal, occlusion and emissive textures.
+	pub maps: [Option<B::Texture>; 4],
+	pub tangents: Option<B::Buffer>, // If there's a normal map.
+}
+
+pub(crate) struct ModelData<B: Backend> {
+	pub(crate) vertex_buffer: B::Buffer,
+	pub(crate) vertex_count: u32,
+	pub(crate) topology: Topology,
+	pub(crate) fans: Vec<(u32, u32)>,
+	// Triangle list of vertex indices, if the model is indexed.
+	pub(crate) indices: Option<Vec<u32>>,
+	pub(crate) normals: Option<B::Buffer>, // For the lit styles.
+}
+
+pub(crate) struct TexcoordsData<B: Backend> {
+	vertex_buffer: B::Buffer,
+	vertex_count: u32,
+	indexed: Option<usize>, // The indexed model it was expanded for.
+}
+
+pub(crate) struct GradientData<B: Backend> {
+	vertex_buffer: B::Buffer,
+	vertex_count: u32,
+	indexed: Option<usize>, // The indexed model it was expanded for.
+}
+
+/// To render anything with adi_gpu, you have to make a `Display`.  It draws
+/// into a window with OpenGL, unless it's a `Headless` one.
+pub struct Display<B: Backend = Gl> {
+	pub(crate) backend: B,
+	pub(crate) id: usize, // For checking handles made by this display.
+	pub(crate) color: (f32, f32, f32),
+	pub(crate) opaque: Shapes<ShapeData<B>>,
+	pub(crate) alpha: Shapes<ShapeData<B>>,
+	pub(crate) gui: Shapes<ShapeData<B>>,
+	pub(crate) models: Resources<ModelData<B>>,
+	pub(crate) texcoords: Resources<TexcoordsData<B>>,
+	pub(crate) gradients: Resources<GradientData<B>>,
+	pub(crate) textures: Resources<B::Texture>,
+	pub(crate) view: Camera,
+	pub(crate) viewports: Vec<Viewport>,
+	pub(crate) ar: f32,
+	pub(crate) lens: Option<Projection>, // `None` for the default projection.
+	pub(crate) projection: Transform,
+	pub(crate) fog: Option<Fog>,
+	pub(crate) sky: Option<fog::Sky>, // Where the fog takes its color from.
+	pub(crate) lights: Vec<Light>,
+}
+
+impl<B: Backend> Display<B> {
+	pub(crate) fn new(backend: B) -> Display<B> {
+		let wh = backend.wh();
+		let ar = wh.0 as f32 / wh.1 as f32;
+		let projection = base::projection(ar, 0.5 * PI);
+		let id = handle::new_display();
+
+		Display {
+			backend,
+			id,
+			color: (0.0, 0.0, 0.0),
+			alpha: Shapes::new(id),
+			opaque: Shapes::new(id),
+			gui: Shapes::new(id),
+			models: Resources::new(id),
+			texcoords: Resources::new(id),
+			gradients: Resources::new(id),
+			textures: Resources::new(id),
+			view: Camera::Euler {
+				xyz: vec3!(0.0, 0.0, 0.0),
+				rotate_xyz: vec3!(0.0, 0.0, 0.0),
+			},
+			viewports: vec![],
+			ar,
+			lens: None,
+			projection,
+			fog: None,
+			sky: None,
+			lights: vec![],
+		}
+	}
+
+	/// Set the layer of a GUI shape (one made without a camera or fog).
+	/// Shapes on higher layers are drawn on top of lower ones, and shapes
+	/// on the same layer are drawn in the order they were made.  The
+	/// default layer is 0.  Does nothing for shapes that aren't GUI.
+	pub fn gui_layer(&mut self, shape: &Shape, layer: i32)
+		-> Result<(), Error>
+	{
+		if let ShapeHandle::Gui(x) = base::get_shape(shape) {
+			self.gui.get_mut(x)?.layer = layer;
+		}
+		Ok(())
+	}
+
+	/// Create a shape from a 2D vector `path`, filled (or stroked) with
+	/// `color`.  Curves stay smooth no matter how far they're scaled.
+	pub fn shape_vector(&mut self, path: &Path, transform: Transform,
+		color: [f32; 4], blending: bool, fog: bool, camera: bool)
+		-> Shape
+	{
+		let (vertices, texcoords) = path.tessellate();
+		let count = vertices.len() as u32 / 4;
+
+		let shape = ShapeData {
+			style: STYLE_VG,
+			buffers: vec![self.backend.buffer(&texcoords)],
+			uniforms: vec![],
+			has_fog: fog,
+			alpha: None,
+			color: Some(color),
+			material: None,
+			transform, // Transformation matrix.
+			layer: 0,
+			cast_shadows: true,
+			receive_shadows: true,
+			texture: None,
+			vertex_buffer: self.backend.buffer(&vertices),
+			topology: Topology::Triangles,
+			fans: vec![(0, count)],
+		};
+
+		self.push_shape(shape, blending, fog, camera)
+	}
+
+	/// Create a model from indexed vertices (4 floats each), drawn with a
+	/// single draw call per shape.  `asi_opengl` has no indexed draw call,
+	/// so the indices are expanded into a triangle list on the CPU, once:
+	/// shared vertices are copied into the vertex buffer for each triangle
+	/// that uses them, so indexing saves no memory or vertex work.  Use
+	/// `texcoords_indexed()` and `gradient_indexed()` for the model's
+	/// texture coordinates and gradients.  Fails if an index is out of
+	/// range.
+	pub fn model_indexed(&mut self, vertices: &[f32], indices: Indices,
+		primitive: Primitive) -> Result<Model, Error>
+	{
+		let list = indexed::triangle_list(&indices, primitive);
+		let vertex_buffer = self.backend
+			.buffer(&indexed::expand(vertices, &list)?);
+
+		Ok(Model(self.models.push(ModelData {
+			vertex_buffer, vertex_count: vertices.len() as u32 / 4,
+			topology: Topology::Triangles,
+			fans: vec![(0, list.len() as u32)],
+			indices: Some(list),
+			normals: None,
+		})))
+	}
+
+	/// Create texture coordinates for a model made with `model_indexed()`,
+	/// with one entry per vertex, not per index.  They can only be used
+	/// with that model, and are expanded for it once, here.
+	pub fn texcoords_indexed(&mut self, model: &Model, texcoords: &[f32])
+		-> Result<TexCoords, Error>
+	{
+		let (data, vertex_count) = self.expand(model, texcoords)?;
+		let vertex_buffer = self.backend.buffer(&data);
+
+		Ok(TexCoords(self.texcoords.push(TexcoordsData {
+			vertex_buffer, vertex_count, indexed: Some(model.0),
+		})))
+	}
+
+	/// Create a gradient for a model made with `model_indexed()`, with one
+	/// color per vertex, not per index.  It can only be used with that
+	/// model, and is expanded for it once, here.
+	pub fn gradient_indexed(&mut self, model: &Model, colors: &[f32])
+		-> Result<Gradient, Error>
+	{
+		let (data, vertex_count) = self.expand(model, colors)?;
+		let vertex_buffer = self.backend.buffer(&data);
+
+		Ok(Gradient(self.gradients.push(GradientData {
+			vertex_buffer, vertex_count, indexed: Some(model.0),
+		})))
+	}
+
+	/// Like `shape_solid()`, but returns an error instead of panicking.
+	pub fn try_shape_solid(&mut self, model: &Model, transform: Transform,
+		color: [f32; 4], blending: bool, fog: bool, camera: bool)
+		-> Result<Shape, Error>
+	{
+		let mut shape = self.shape_data(STYLE_SOLID, model, vec![],
+			None, transform, fog)?;
+		shape.color = Some(color);
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Like `shape_gradient()`, but returns an error instead of panicking.
+	pub fn try_shape_gradient(&mut self, model: &Model,
+		transform: Transform, colors: Gradient, blending: bool, fog: bool,
+		camera: bool) -> Result<Shape, Error>
+	{
+		self.check_gradient(model, &colors)?;
+
+		let buffers = vec![self.gradient_buffer(&colors)];
+		let shape = self.shape_data(STYLE_GRADIENT, model, buffers,
+			None, transform, fog)?;
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Like `shape_texture()`, but returns an error instead of panicking.
+	pub fn try_shape_texture(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, blending: bool, fog: bool,
+		camera: bool) -> Result<Shape, Error>
+	{
+		self.check_texcoords(model, &tc)?;
+
+		let buffers = vec![self.texcoords_buffer(&tc)];
+		let shape = self.shape_data(STYLE_TEXTURE, model, buffers,
+			Some(texture), transform, fog)?;
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Like `shape_faded()`, but returns an error instead of panicking.
+	pub fn try_shape_faded(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, alpha: f32, fog: bool,
+		camera: bool) -> Result<Shape, Error>
+	{
+		self.check_texcoords(model, &tc)?;
+
+		let buffers = vec![self.texcoords_buffer(&tc)];
+		let mut shape = self.shape_data(STYLE_FADED, model, buffers,
+			Some(texture), transform, fog)?;
+		shape.alpha = Some(alpha);
+
+		// Faded shapes always need blending.
+		Ok(self.push_shape(shape, true, fog, camera))
+	}
+
+	/// Like `shape_tinted()`, but returns an error instead of panicking.
+	pub fn try_shape_tinted(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, tint: [f32; 4], blending: bool,
+		fog: bool, camera: bool) -> Result<Shape, Error>
+	{
+		self.check_texcoords(model, &tc)?;
+
+		let buffers = vec![self.texcoords_buffer(&tc)];
+		let mut shape = self.shape_data(STYLE_TINTED, model, buffers,
+			Some(texture), transform, fog)?;
+		shape.color = Some(tint);
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Like `shape_complex()`, but returns an error instead of panicking.
+	pub fn try_shape_complex(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, tints: Gradient,
+		blending: bool, fog: bool, camera: bool) -> Result<Shape, Error>
+	{
+		self.check_texcoords(model, &tc)?;
+		self.check_gradient(model, &tints)?;
+
+		let buffers = vec![
+			self.texcoords_buffer(&tc),
+			self.gradient_buffer(&tints),
+		];
+		let shape = self.shape_data(STYLE_COMPLEX, model, buffers,
+			Some(texture), transform, fog)?;
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Give a model normals (4 floats per vertex, like the vertices), so it
+	/// can be drawn with the lit styles.
+	pub fn set_normals(&mut self, model: &Model, normals: &[f32])
+		-> Result<(), Error>
+	{
+		let model = self.models.get_mut(model.0)?;
+
+		if normals.len() as u32 / 4 != model.vertex_count {
+			return Err(Error::VertexCountMismatch);
+		}
+
+		model.normals = Some(match model.indices {
+			Some(ref list) => self.backend
+				.buffer(&indexed::expand(normals, list)?),
+			None => self.backend.buffer(normals),
+		});
+		Ok(())
+	}
+
+	/// Set the lights shining on shapes made with the lit styles,
+	/// replacing the ones set before.  With no lights (the default), lit
+	/// shapes are black.  Fails if there are more than `MAX_LIGHTS`, or a
+	/// spot light's angle isn't between 0 and a quarter turn.
+	pub fn light(&mut self, lights: &[Light]) -> Result<(), Error> {
+		if lights.len() > MAX_LIGHTS {
+			return Err(Error::TooManyLights);
+		}
+
+		for light in lights.iter() {
+			if let Light::Spot { angle, .. } = *light {
+				if !(angle > 0.0 && angle < ::std::f32::consts::FRAC_PI_2) {
+					return Err(Error::InvalidLight);
+				}
+			}
+		}
+
+		self.lights = lights.to_vec();
+		Ok(())
+	}
+
+	/// Set how fog thickens and its color, or turn it off with `None`.
+	/// This is more flexible than `fog()`, which only sets cubic fog in
+	/// the clear color.
+	pub fn set_fog(&mut self, fog: Option<Fog>) {
+		self.fog = fog;
+	}
+
+	/// Make fog take its color from a sky panorama, so that shapes fade
+	/// into a skybox, or go back to the fog's own color with `None`.  `sky`
+	/// is `wh` pixels (like for `texture()`), mapped around the Y axis
+	/// with up at the top.  Each shape fades into the sky's horizon behind
+	/// it, blended from 16 colors around it, so the fog doesn't follow the
+	/// sky above or below the horizon.  Sampling the whole sky needs a
+	/// second texture bound while drawing, which `asi_opengl` can't do
+	/// yet.
+	pub fn set_fog_sky(&mut self, sky: Option<((u16, u16), &VFrame)>) {
+		self.sky = sky.and_then(|sky| fog::sky(sky.0, sky.1));
+	}
+
+	/// Like `shape_solid()`, but lit by the lights from `light()`.  The
+	/// model needs normals, from `set_normals()`.
+	pub fn shape_lit_solid(&mut self, model: &Model, transform: Transform,
+		color: [f32; 4], blending: bool, fog: bool, camera: bool)
+		-> Result<Shape, Error>
+	{
+		let buffers = vec![self.normals_buffer(model)?];
+		let mut shape = self.shape_data(STYLE_LIT_SOLID, model, buffers,
+			None, transform, fog)?;
+		shape.color = Some(color);
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Like `shape_texture()`, but lit by the lights from `light()`.  The
+	/// model needs normals, from `set_normals()`.
+	pub fn shape_lit_texture(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, blending: bool, fog: bool,
+		camera: bool) -> Result<Shape, Error>
+	{
+		self.check_texcoords(model, &tc)?;
+
+		let buffers = vec![
+			self.texcoords_buffer(&tc),
+			self.normals_buffer(model)?,
+		];
+		let shape = self.shape_data(STYLE_LIT_TEXTURE, model, buffers,
+			Some(texture), transform, fog)?;
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Like `shape_tinted()`, but lit by the lights from `light()`.  The
+	/// model needs normals, from `set_normals()`.
+	pub fn shape_lit_tinted(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, tint: [f32; 4], blending: bool,
+		fog: bool, camera: bool) -> Result<Shape, Error>
+	{
+		self.check_texcoords(model, &tc)?;
+
+		let buffers = vec![
+			self.texcoords_buffer(&tc),
+			self.normals_buffer(model)?,
+		];
+		let mut shape = self.shape_data(STYLE_LIT_TINTED, model, buffers,
+			Some(texture), transform, fog)?;
+		shape.color = Some(tint);
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Create a shape with a physically-based `material`, lit by the lights
+	/// from `light()`.  The model needs normals, from `set_normals()`.
+	/// `asi_opengl` can only bind one texture at a time, so on the OpenGL
+	/// `Display` only the base color texture is supported, and other
+	/// textures fail with `Error::Unsupported`; `Headless` supports all of
+	/// them.
+	pub fn shape_pbr(&mut self, model: &Model, transform: Transform,
+		material: &Material, tc: TexCoords, blending: bool, fog: bool,
+		camera: bool) -> Result<Shape, Error>
+	{
+		self.check_texcoords(model, &tc)?;
+
+		let texcoords = self.texcoords_buffer(&tc);
+		let normals = self.normals_buffer(model)?;
+		let mut maps = [None, None, None, None];
+		for (i, map) in material.maps().iter().enumerate() {
+			if let Some(map) = *map {
+				maps[i] = Some(self.textures.get(map.0)?.clone());
+			}
+		}
+		let tangents = if maps.iter().any(|map| map.is_some()) {
+			let model = self.models.get(model.0)?;
+
+			self.backend.material_maps(&model.vertex_buffer,
+				&texcoords, &normals, model.topology, &model.fans,
+				material.normal_texture.is_some())?
+		} else {
+			None
+		};
+
+		let buffers = vec![texcoords, normals];
+		let mut shape = self.shape_data(STYLE_PBR, model, buffers,
+			material.base_color_texture, transform, fog)?;
+		shape.color = Some(material.base_color);
+		shape.material = Some(MaterialData {
+			factors: material.factors(),
+			maps,
+			tangents,
+		});
+
+		Ok(self.push_shape(shape, blending, fog, camera))
+	}
+
+	/// Like `drop_shape()`, but returns an error instead of panicking.
+	pub fn try_drop_shape(&mut self, shape: &Shape) -> Result<(), Error> {
+		match base::get_shape(shape) {
+			ShapeHandle::Opaque(x) => self.opaque.remove(x),
+			ShapeHandle::Alpha(x) => self.alpha.remove(x),
+			ShapeHandle::Gui(x) => self.gui.remove(x),
+		}.map(|_| ())
+	}
+
+	/// Like `transform()`, but returns an error instead of panicking.
+	pub fn try_transform(&mut self, shape: &Shape, transform: Transform)
+		-> Result<(), Error>
+	{
+		self.shape_mut(shape)?.transform = transform;
+		Ok(())
+	}
+
+	/// Set how the scene is projected onto the screen, replacing the
+	/// default 90 degree perspective.  Fails unless `0 < near < far`, the
+	/// field of view is between 0 and π, and the height is positive.
+	///
+	/// `asi_opengl` can't set a `GREATER` depth test, so the OpenGL
+	/// `Display` mirrors reversed-Z depth back (z becomes -z) in the
+	/// vertex shader before the `LESS` test.  That puts the depth back
+	/// where a normal projection has it, so reversed-Z gives no precision
+	/// benefit there; shapes are only drawn the same.
+	pub fn set_projection(&mut self, projection: Projection)
+		-> Result<(), Error>
+	{
+		projection.check()?;
+
+		self.lens = Some(projection);
+		self.projection = self.projection_transform();
+		Ok(())
+	}
+
+	/// Set where the camera is and which way it faces.  This is more
+	/// flexible than `camera()`, which only moves, then rotates by Euler
+	/// angles.
+	pub fn set_camera(&mut self, camera: Camera) {
+		self.view = camera;
+	}
+
+	/// Split the window into viewports, each drawing the opaque and alpha
+	/// shapes with its own camera, for split screen or picture-in-picture.
+	/// Later viewports are drawn over earlier ones, and GUI shapes are
+	/// drawn once over all of them.  With no viewports (the default), the
+	/// whole window is drawn with the camera from `set_camera()`.
+	pub fn set_viewports(&mut self, viewports: &[Viewport]) {
+		self.viewports = viewports.to_vec();
+	}
+
+	/// Like `set_texture()`, but returns an error instead of panicking.
+	pub fn try_set_texture(&mut self, texture: &mut Texture,
+		wh: (u16, u16), graphic: &VFrame) -> Result<(), Error>
+	{
+		let t = self.textures.get(texture.0)?;
+
+		self.backend.set_texture(t, wh, graphic.0.as_slice());
+		Ok(())
+	}
+
+	/// Drop a model.  Shapes already made from it keep drawing, and its
+	/// vertex buffer is freed once they're all dropped.
+	pub fn drop_model(&mut self, model: Model) -> Result<(), Error> {
+		self.models.remove(model.0).map(|_| ())
+	}
+
+	/// Drop a texture.  Shapes already using it keep drawing, and it's
+	/// freed once they're all dropped.
+	pub fn drop_texture(&mut self, texture: Texture) -> Result<(), Error> {
+		self.textures.remove(texture.0).map(|_| ())
+	}
+
+	/// Drop texture coordinates.  They're freed once all shapes using them
+	/// are dropped.
+	pub fn drop_texcoords(&mut self, tc: TexCoords) -> Result<(), Error> {
+		self.texcoords.remove(tc.0).map(|_| ())
+	}
+
+	/// Drop a gradient.  It's freed once all shapes using it are dropped.
+	pub fn drop_gradient(&mut self, gradient: Gradient) -> Result<(), Error> {
+		self.gradients.remove(gradient.0).map(|_| ())
+	}
+
+	// Draw the opaque and alpha shapes (only those in `only`, if it's
+	// `Some`) seen from `view` through `camera` (its view and projection
+	// matrix), then `squeeze`, inside of `rect` in pixels.
+	pub(crate) fn draw_world(&mut self, view: Camera, camera: Transform,
+		squeeze: Transform, rect: (u16, u16, u16, u16),
+		only: Option<&Selection>)
+	{
+		let xyz = view.eye();
+		let pass = Pass {
+			camera: Some(camera),
+			squeeze,
+			viewport: rect,
+			eye: xyz,
+			fog: self.fog_uniforms(),
+			sky: self.sky,
+			lights: self.lights.clone(),
+			depth_test: true,
+			reversed_z: self.reversed_z(),
+		};
+
+		self.backend.pass(&pass);
+
+		// Opaque shapes nearest first, then alpha shapes farthest first.
+		self.opaque.sort(true, xyz);
+		for shape in self.opaque.iter_in(only.map(|s| &s.opaque[..])) {
+			self.backend.draw(shape);
+		}
+
+		self.alpha.sort(false, xyz);
+		for shape in self.alpha.iter_in(only.map(|s| &s.alpha[..])) {
+			self.backend.draw(shape);
+		}
+	}
+
+	// Get the `fog` and `range` uniforms.
+	fn fog_uniforms(&self) -> ([f32; 4], [f32; 4]) {
+		fog::uniforms(self.fog, self.color)
+	}
+
+	fn projection_transform(&self) -> Transform {
+		projection::transform(self.lens, self.ar)
+	}
+
+	// Whether the depth buffer is reversed, with near shapes at 1.
+	pub(crate) fn reversed_z(&self) -> bool {
+		self.lens.map(|lens| lens.reversed_z()).unwrap_or(false)
+	}
+
+	pub(crate) fn shape_mut(&mut self, shape: &Shape)
+		-> Result<&mut ShapeData<B>, Error>
+	{
+		match base::get_shape(shape) {
+			ShapeHandle::Opaque(x) => self.opaque.get_mut(x),
+			ShapeHandle::Alpha(x) => self.alpha.get_mut(x),
+			ShapeHandle::Gui(x) => self.gui.get_mut(x),
+		}
+	}
+
+	// Check that the texture coordinates match the model's vertices.
+	fn check_texcoords(&self, model: &Model, tc: &TexCoords)
+		-> Result<(), Error>
+	{
+		let texcoords = self.texcoords.get(tc.0)?;
+
+		self.check_attribute(model, texcoords.vertex_count,
+			texcoords.indexed)
+	}
+
+	// Check that the gradient matches the model's vertices.
+	fn check_gradient(&self, model: &Model, gradient: &Gradient)
+		-> Result<(), Error>
+	{
+		let gradient = self.gradients.get(gradient.0)?;
+
+		self.check_attribute(model, gradient.vertex_count,
+			gradient.indexed)
+	}
+
+	// Check that texture coordinates or a gradient, expanded for the
+	// `indexed` model if any, can be used with a model.
+	fn check_attribute(&self, model: &Model, vertex_count: u32,
+		indexed: Option<usize>) -> Result<(), Error>
+	{
+		let data = self.models.get(model.0)?;
+		let matches = match data.indices {
+			Some(_) => indexed == Some(model.0),
+			None => indexed.is_none()
+				&& data.vertex_count == vertex_count,
+		};
+
+		if !matches {
+			return Err(Error::VertexCountMismatch);
+		}
+		Ok(())
+	}
+
+	// Expand a vertex attribute for an indexed model, returning it with
+	// how many vertices the model has.
+	fn expand(&self, model: &Model, data: &[f32])
+		-> Result<(Vec<f32>, u32), Error>
+	{
+		let model = self.models.get(model.0)?;
+		let list = model.indices.as_ref().ok_or(Error::InvalidHandle)?;
+
+		if data.len() as u32 / 4 != model.vertex_count {
+			return Err(Error::VertexCountMismatch);
+		}
+
+		Ok((indexed::expand(data, list)?, model.vertex_count))
+	}
+
+	// Build the shape data common to all of the styles.
+	pub(crate) fn shape_data(&self, style: usize, model: &Model,
+		buffers: Vec<B::Buffer>, texture: Option<&Texture>,
+		transform: Transform, fog: bool) -> Result<ShapeData<B>, Error>
+	{
+		let model = self.models.get(model.0)?;
+		let texture = match texture {
+			Some(texture) => Some(self.textures.get(texture.0)?.clone()),
+			None => None,
+		};
+
+		Ok(ShapeData {
+			style,
+			buffers,
+			uniforms: vec![],
+			has_fog: fog,
+			alpha: None,
+			color: None,
+			material: None,
+			transform, // Transformation matrix.
+			layer: 0,
+			cast_shadows: true,
+			receive_shadows: true,
+			texture,
+			vertex_buffer: model.vertex_buffer.clone(),
+			topology: model.topology,
+			fans: model.fans.clone(),
+		})
+	}
+
+	// Get the buffer of texture coordinates, checked by
+	// `check_texcoords()`.
+	fn texcoords_buffer(&self, tc: &TexCoords) -> B::Buffer {
+		self.texcoords.get(tc.0).unwrap().vertex_buffer.clone()
+	}
+
+	// Get the buffer of gradient colors, checked by `check_gradient()`.
+	fn gradient_buffer(&self, gradient: &Gradient) -> B::Buffer {
+		self.gradients.get(gradient.0).unwrap().vertex_buffer.clone()
+	}
+
+	// Get the buffer of normals for a model.
+	fn normals_buffer(&self, model: &Model) -> Result<B::Buffer, Error> {
+		self.models.get(model.0)?.normals.clone().ok_or(Error::NoNormals)
+	}
+
+	// Upload a vertex attribute for a model, expanded to match the vertices
+	// if the model is indexed.
+	pub(crate) fn attribute_buffer(&mut self, model: &Model, data: &[f32])
+		-> Result<B::Buffer, Error>
+	{
+		Ok(match self.models.get(model.0)?.indices {
+			Some(ref list) => self.backend
+				.buffer(&indexed::expand(data, list)?),
+			None => self.backend.buffer(data),
+		})
+	}
+
+	// Add a shape to the gui, alpha or opaque list.
+	pub(crate) fn push_shape(&mut self, shape: ShapeData<B>, blending: bool,
+		fog: bool, camera: bool) -> Shape
+	{
+		base::new_shape(if !camera && !fog {
+			base::ShapeHandle::Gui(self.gui.push(shape))
+		} else if blending {
+			base::ShapeHandle::Alpha(self.alpha.push(shape))
+		} else {
+			base::ShapeHandle::Opaque(self.opaque.push(shape))
+		})
+	}
+}
+
+impl<B: Backend> base::Display for Display<B> {
+	fn color(&mut self, color: (f32, f32, f32)) {
+		self.color = color;
+	}
+
+	fn update(&mut self) -> Option<adi_gpu_base::Input> {
+		let reversed_z = self.reversed_z();
+
+		if let Some(input) = self.backend.begin(self.color, reversed_z) {
+			return Some(input);
+		}
+
+		B::before_world(self);
+
+		let wh = self.backend.wh();
+		if self.viewports.is_empty() {
+			let view = self.view;
+			let camera = view.transform()
+				.m(self.projection.0); // Apply projection to camera
+
+			self.draw_world(view, camera, Transform::IDENTITY,
+				(0, 0, wh.0, wh.1), None);
+		} else {
+			let n = self.viewports.len();
+
+			for i in 0..n {
+				let viewport = self.viewports[i];
+				let camera = viewport.transform(self.lens, wh);
+				let squeeze = viewport.squeeze(self.lens, i, n);
+
+				self.draw_world(viewport.camera, camera, squeeze,
+					viewport.pixels(wh), None);
+			}
+		}
+
+		B::after_world(self);
+
+		// No camera or depth testing for gui elements, which are drawn
+		// from lowest to highest layer.
+		let pass = Pass {
+			camera: None,
+			squeeze: Transform::IDENTITY,
+			viewport: (0, 0, wh.0, wh.1),
+			eye: self.view.eye(),
+			fog: self.fog_uniforms(),
+			sky: self.sky,
+			lights: self.lights.clone(),
+			depth_test: false,
+			reversed_z: false,
+		};
+		self.backend.pass(&pass);
+		self.gui.sort_by_key(|shape| shape.layer);
+		for shape in self.gui.iter() {
+			self.backend.draw(shape);
+		}
+
+		self.backend.end();
+		// Return None, there was no input, updated screen.
+		None
+	}
+
+	fn camera(&mut self, xyz: Vec3, rotate_xyz: Vec3) {
+		self.set_camera(Camera::Euler { xyz, rotate_xyz });
+	}
+
+	fn model(&mut self, vertices: &[f32], fans: Vec<(u32, u32)>) -> Model {
+		let vertex_buffer = self.backend.buffer(vertices);
+
+		Model(self.models.push(ModelData {
+			vertex_buffer, vertex_count: vertices.len() as u32 / 4,
+			topology: Topology::TriangleFan, fans, indices: None,
+			normals: None,
+		}))
+	}
+
+	fn fog(&mut self, fog: Option<(f32, f32)>) -> () {
+		self.set_fog(fog.map(|fog| Fog::cubic(fog.0, fog.1)));
+	}
+
+	fn texture(&mut self, wh: (u16,u16), graphic: &VFrame) -> Texture {
+		let t = self.backend.texture(wh, graphic.0.as_slice());
+
+		Texture(self.textures.push(t), wh.0, wh.1)
+	}
+
+	fn gradient(&mut self, colors: &[f32]) -> Gradient {
+		let vertex_buffer = self.backend.buffer(colors);
+
+		Gradient(self.gradients.push(GradientData {
+			vertex_buffer,
+			vertex_count: colors.len() as u32 / 4,
+			indexed: None,
+		}))
+	}
+
+	fn texcoords(&mut self, texcoords: &[f32]) -> TexCoords {
+		let vertex_buffer = self.backend.buffer(texcoords);
+
+		TexCoords(self.texcoords.push(TexcoordsData {
+			vertex_buffer,
+			vertex_count: texcoords.len() as u32 / 4,
+			indexed: None,
+		}))
+	}
+
+	fn set_texture(&mut self, texture: &mut Texture, wh: (u16,u16),
+		graphic: &VFrame)
+	{
+		self.try_set_texture(texture, wh, graphic).unwrap()
+	}
+
+	#[inline(always)]
+	fn shape_solid(&mut self, model: &Model, transform: Transform,
+		color: [f32; 4], blending: bool, fog: bool, camera: bool)
+		-> Shape
+	{
+		self.try_shape_solid(model, transform, color, blending, fog,
+			camera).unwrap()
+	}
+
+	#[inline(always)]
+	fn shape_gradient(&mut self, model: &Model, transform: Transform,
+		colors: Gradient, blending: bool, fog: bool, camera: bool)
+		-> Shape
+	{
+		self.try_shape_gradient(model, transform, colors, blending, fog,
+			camera).unwrap()
+	}
+
+	#[inline(always)]
+	fn shape_texture(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, blending: bool, fog: bool,
+		camera: bool) -> Shape
+	{
+		self.try_shape_texture(model, transform, texture, tc, blending,
+			fog, camera).unwrap()
+	}
+
+	#[inline(always)]
+	fn shape_faded(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, alpha: f32, fog: bool,
+		camera: bool) -> Shape
+	{
+		self.try_shape_faded(model, transform, texture, tc, alpha, fog,
+			camera).unwrap()
+	}
+
+	#[inline(always)]
+	fn shape_tinted(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, tint: [f32; 4], blending: bool,
+		fog: bool, camera: bool) -> Shape
+	{
+		self.try_shape_tinted(model, transform, texture, tc, tint,
+			blending, fog, camera).unwrap()
+	}
+
+	#[inline(always)]
+	fn shape_complex(&mut self, model: &Model, transform: Transform,
+		texture: &Texture, tc: TexCoords, tints: Gradient,
+		blending: bool, fog: bool, camera: bool) -> Shape
+	{
+		self.try_shape_complex(model, transform, texture, tc, tints,
+			blending, fog, camera).unwrap()
+	}
+
+	#[inline(always)]
+	fn drop_shape(&mut self, shape: &Shape) {
+		self.try_drop_shape(shape).unwrap()
+	}
+
+	fn transform(&mut self, shape: &Shape, transform: Transform) {
+		self.try_transform(shape, transform).unwrap()
+	}
+
+	fn resize(&mut self, wh: (u16, u16)) -> () {
+		self.ar = wh.0 as f32 / wh.1 as f32;
+		self.backend.resize(wh);
+
+		self.projection = self.projection_transform();
+	}
+
+	fn wh(&self) -> (u16, u16) {
+		self.backend.wh()
+	}
+}
@@ -0,0 +1,84 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Errors returned by the `try_*` methods on `Display`.
+
+use std::fmt;
+
+/// An error from a `Display`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+	/// The texture coordinates, gradient or normals don't have one entry
+	/// for each vertex of the model.
+	VertexCountMismatch,
+	/// A handle doesn't refer to anything on the `Display`.
+	InvalidHandle,
+	/// A shape was dropped more than once.
+	DoubleDrop,
+	/// A handle refers to a resource that was dropped.
+	StaleHandle,
+	/// A handle was made by a different `Display`.
+	WrongDisplay,
+	/// An index refers to a vertex that doesn't exist.
+	IndexOutOfRange,
+	/// A lit style was used with a model that doesn't have normals.
+	NoNormals,
+	/// More than `MAX_LIGHTS` lights were set.
+	TooManyLights,
+	/// The near and far planes, field of view or height of a projection
+	/// are out of range.
+	InvalidProjection,
+	/// A spot light's angle isn't more than 0 and less than a quarter
+	/// turn.
+	InvalidLight,
+	/// The `Display` can't do this, because `asi_opengl` doesn't wrap the
+	/// parts of OpenGL it needs, or OpenGL isn't supported on the platform.
+	Unsupported(String),
+	/// A mesh file couldn't be imported.
+	Import(&'static str),
+	/// A style's shaders failed to compile or link.
+	Shader(String),
+	/// OpenGL failed.
+	OpenGL(String),
+}
+
+/// The uniforms and attributes a style's shaders should have, but don't,
+/// from `Display::validate_styles()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleReport {
+	/// The name of the style, like "solid" or "custom 7".
+	pub style: String,
+	/// The names of the missing uniforms and attributes.
+	pub missing: Vec<String>,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::VertexCountMismatch => write!(f, "TexCoord, \
+				gradient or normal length doesn't match vertex \
+				length"),
+			Error::InvalidHandle => write!(f, "Invalid handle"),
+			Error::DoubleDrop => write!(f, "Shape dropped twice"),
+			Error::StaleHandle => write!(f, "Handle to dropped \
+				resource"),
+			Error::WrongDisplay => write!(f, "Handle from another \
+				display"),
+			Error::IndexOutOfRange => write!(f, "Index out of range"),
+			Error::NoNormals => write!(f, "Model has no normals"),
+			Error::TooManyLights => write!(f, "Too many lights"),
+			Error::InvalidProjection => write!(f, "Invalid \
+				projection"),
+			Error::InvalidLight => write!(f, "Invalid light"),
+			Error::Unsupported(ref e) => write!(f, "Unsupported: {}",
+				e),
+			Error::Import(e) => write!(f, "Import: {}", e),
+			Error::Shader(ref e) => write!(f, "Shader: {}", e),
+			Error::OpenGL(ref e) => write!(f, "OpenGL: {}", e),
+		}
+	}
+}
+
+impl ::std::error::Error for Error {}
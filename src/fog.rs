@@ -0,0 +1,160 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Fog for `Display::set_fog()` and `Display::set_fog_sky()`.  `amount()`
+//! and `color()` follow `fog_amount()` and `fog_color()` in
+//! `src/shaders/fog.glsl`.
+
+use adi_gpu_base::*;
+
+/// How many colors around the horizon of a sky from `set_fog_sky()` are passed
+/// to the shaders.
+pub(crate) const SKY_RING: usize = 16;
+
+/// How fog thickens with distance from the camera.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FogMode {
+	/// Thickens slowly from `start`, then quickly towards `start + range`,
+	/// like `Display::fog()`.
+	Cubic {
+		start: f32,
+		range: f32,
+	},
+	/// Thickens evenly from `start` to `start + range`.
+	Linear {
+		start: f32,
+		range: f32,
+	},
+	/// `1 - e^(-density * distance)`.
+	Exponential {
+		density: f32,
+	},
+	/// `1 - e^(-(density * distance)²)`, which stays clear for longer.
+	ExponentialSquared {
+		density: f32,
+	},
+}
+
+/// Fog settings, for `Display::set_fog()`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fog {
+	/// How fog thickens with distance.
+	pub mode: FogMode,
+	/// The fog color, or `None` to follow the clear color from `color()`,
+	/// even when it changes later.  `set_fog_sky()` overrides either.
+	pub color: Option<(f32, f32, f32)>,
+	/// Height fog: above a base height, the fog thins out by a falloff
+	/// (`e^(-falloff * height above base)`).  Heights are measured up, along
+	/// -Y.
+	pub height: Option<(f32, f32)>,
+}
+
+impl Fog {
+	/// Fog like `Display::fog()` sets up: cubic, in the clear color.
+	pub fn cubic(start: f32, range: f32) -> Fog {
+		Fog {
+			mode: FogMode::Cubic { start, range },
+			color: None,
+			height: None,
+		}
+	}
+}
+
+// Colors around the horizon of a sky panorama, that fog takes its color from.
+pub(crate) type Sky = [[f32; 4]; SKY_RING];
+
+// Sample `SKY_RING` colors evenly around the horizon of an (equirectangular)
+// panorama, starting behind, with an alpha of 1.  `None` if there aren't
+// enough pixels.
+pub(crate) fn sky(wh: (u16, u16), graphic: &VFrame) -> Option<Sky> {
+	let (w, h) = (wh.0 as usize, wh.1 as usize);
+
+	if w == 0 || h == 0 || graphic.0.len() < w * h {
+		return None;
+	}
+
+	let mut ring = [[0.0; 4]; SKY_RING];
+	for (i, color) in ring.iter_mut().enumerate() {
+		let s = (i as f32 + 0.5) / SKY_RING as f32;
+		let x = ((s * w as f32) as usize).min(w - 1);
+		let pixel = graphic.0[(h / 2) * w + x];
+		let channel = |shift: u32| ((pixel >> shift) & 0xFF) as f32 / 255.0;
+
+		*color = [channel(0), channel(8), channel(16), 1.0];
+	}
+
+	Some(ring)
+}
+
+// Get the `fog` (color and mode) and `range` (start, range or density, height
+// fog base and falloff) uniforms.  No fog starts past the far clip.
+pub(crate) fn uniforms(fog: Option<Fog>, clear: (f32, f32, f32))
+	-> ([f32; 4], [f32; 4])
+{
+	let fog = match fog {
+		Some(fog) => fog,
+		None => return ([clear.0, clear.1, clear.2, 0.0],
+			[::std::f32::MAX, 0.0, 0.0, 0.0]),
+	};
+	let color = fog.color.unwrap_or(clear);
+	let (mode, x, y) = match fog.mode {
+		FogMode::Cubic { start, range } => (0.0, start, range),
+		FogMode::Linear { start, range } => (1.0, start, range),
+		FogMode::Exponential { density } => (2.0, 0.0, density),
+		FogMode::ExponentialSquared { density } => (3.0, 0.0, density),
+	};
+	let (base, falloff) = fog.height.unwrap_or((0.0, 0.0));
+
+	([color.0, color.1, color.2, mode], [x, y, base, falloff])
+}
+
+// Get how much fog there is, 0 to 1, `z` away at `height`.
+pub(crate) fn amount(fog: [f32; 4], range: [f32; 4], z: f32, height: f32)
+	-> f32
+{
+	let mut amount = if fog[3] < 0.5 {
+		let linear = ((z - range[0]) / range[1]).clamp(0.0, 1.0);
+		linear * linear * linear
+	} else if fog[3] < 1.5 {
+		((z - range[0]) / range[1]).clamp(0.0, 1.0)
+	} else if fog[3] < 2.5 {
+		1.0 - (-range[1] * z).exp()
+	} else {
+		let d = range[1] * z;
+		1.0 - (-d * d).exp()
+	};
+
+	if range[3] > 0.0 {
+		amount *= (-(height - range[2]).max(0.0) * range[3]).exp();
+	}
+
+	amount
+}
+
+// Get the fog color, like `fog_color()`: blended from the two colors of `sky`
+// nearest the direction `around` (x and z in the world) from the camera, or
+// `fog`'s own without a sky.
+pub(crate) fn color(fog: [f32; 4], sky: Option<&Sky>, around: [f32; 2])
+	-> [f32; 3]
+{
+	let sky = match sky {
+		Some(sky) => sky,
+		None => return [fog[0], fog[1], fog[2]],
+	};
+	let n = SKY_RING as f32;
+	let s = (around[0].atan2(around[1]) / (2.0 * PI) + 0.5) * n - 0.5;
+	let mut color = [0.0; 3];
+
+	for (i, sample) in sky.iter().enumerate() {
+		let d = (s - i as f32).abs();
+		let weight = (1.0 - d.min(n - d)).max(0.0);
+
+		for j in 0..3 {
+			color[j] += sample[j] * weight;
+		}
+	}
+
+	color
+}
@@ -0,0 +1,72 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Handles pack a slot index, the id of the display that made them and the
+//! generation of the slot, so handles from another display or to something
+//! dropped are caught.  Shape handles are only 32 bits, so their generation
+//! wraps after 256 drops of the same slot, and display ids wrap after 16.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use Error;
+
+const INDEX_BITS: usize = 20;
+const DISPLAY_BITS: usize = 4;
+const INDEX_MASK: usize = (1 << INDEX_BITS) - 1;
+const DISPLAY_MASK: usize = (1 << DISPLAY_BITS) - 1;
+
+static DISPLAYS: AtomicUsize = AtomicUsize::new(0);
+
+/// Get an id for a new display.
+pub fn new_display() -> usize {
+	DISPLAYS.fetch_add(1, Ordering::Relaxed) & DISPLAY_MASK
+}
+
+/// Make a handle.
+pub fn pack(index: usize, display: usize, generation: usize) -> usize {
+	assert!(index <= INDEX_MASK, "Too many handles on one display");
+
+	index | (display << INDEX_BITS)
+		| (generation << (INDEX_BITS + DISPLAY_BITS))
+}
+
+/// Get the slot index and generation of a handle, checking that it was made
+/// by `display`.
+pub fn unpack(handle: usize, display: usize) -> Result<(usize, usize), Error> {
+	if (handle >> INDEX_BITS) & DISPLAY_MASK != display {
+		return Err(Error::WrongDisplay);
+	}
+
+	Ok((handle & INDEX_MASK, handle >> (INDEX_BITS + DISPLAY_BITS)))
+}
+
+/// The generation after `generation`, wrapping to fit in a handle of type
+/// `max` (`u32::MAX` or `usize::MAX`).
+pub fn next_generation(generation: usize, max: usize) -> usize {
+	(generation + 1) & (max >> (INDEX_BITS + DISPLAY_BITS))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn round_trip() {
+		let handle = pack(12345, 7, 3);
+
+		assert_eq!(unpack(handle, 7), Ok((12345, 3)));
+		assert_eq!(unpack(handle, 6), Err(Error::WrongDisplay));
+	}
+
+	#[test]
+	fn generations_wrap() {
+		let max = ::std::u32::MAX as usize;
+
+		assert_eq!(next_generation(0, max), 1);
+		assert_eq!(next_generation(255, max), 0);
+		assert_eq!(unpack(pack(1, 2, 255) as u32 as usize, 2),
+			Ok((1, 255)));
+	}
+}
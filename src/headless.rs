@@ -0,0 +1,362 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Headless `Display`, which renders into memory instead of a window.
+
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+use adi_gpu_base as base;
+use adi_gpu_base::*;
+use asi_opengl::Topology;
+
+use display::{Backend, Display, Pass, Selection, ShapeData};
+use raster::{self, Frame, Image, Uniforms, Attributes};
+use STYLE_ATTRIBUTES;
+use camera::Camera;
+use post::PostProcess;
+use projection;
+use Error;
+use material;
+use shadow::{ShadowMap, Shadows};
+
+/// A `Display` without a window, for machines without a screen or GPU.
+/// Shapes are drawn into memory by a software rasterizer on `update()`, which
+/// follows the same shaders as the OpenGL `Display`, so frames can be used as
+/// golden images.
+pub type Headless = Display<Software>;
+
+/// The software backend of `Headless`, which draws into memory.
+pub struct Software {
+	frame: Frame,
+	pass: Option<Pass>, // The uniforms from the last `pass()`.
+	shadows: Option<Shadows>,
+	shadow_maps: Vec<Option<ShadowMap>>, // For this frame, for each light.
+	targets: Vec<RenderTarget>,
+	post: Vec<PostProcess>,
+}
+
+// A texture that shapes are drawn into.
+struct RenderTarget {
+	texture: usize, // The texture's handle.
+	wh: (u16, u16),
+	camera: Camera,
+	shapes: Option<Selection>, // `None` for all of the shapes.
+}
+
+impl Software {
+	pub(crate) fn new(wh: (u16, u16)) -> Software {
+		Software {
+			frame: Frame::new(wh),
+			pass: None,
+			shadows: None,
+			shadow_maps: vec![],
+			targets: vec![],
+			post: vec![],
+		}
+	}
+}
+
+impl Display<Software> {
+	/// Get the frame drawn by the last `update()`, as RGBA pixels with rows
+	/// ordered top to bottom.  The OpenGL `Display` can't do this until
+	/// `asi_opengl` can read pixels back.
+	pub fn screenshot(&self) -> VFrame {
+		VFrame(self.backend.frame.pixels())
+	}
+
+	/// Compare the last frame against a golden image of the same size,
+	/// returning how many pixels differ by more than `tolerance` in any
+	/// channel.
+	pub fn diff(&self, golden: &VFrame, tolerance: u8) -> usize {
+		raster::diff(&self.backend.frame.pixels(), &golden.0, tolerance)
+	}
+
+	/// Create a `wh` texture that the opaque and alpha shapes are drawn
+	/// into from `camera` at the start of every `update()`, so it can be
+	/// used with `shape_texture()` for mirrors or monitors.  Shapes using
+	/// the texture while it's drawn see the previous frame.  The OpenGL
+	/// `Display` can't do this until `asi_opengl` has framebuffer objects.
+	pub fn create_render_target(&mut self, wh: (u16, u16), camera: Camera)
+		-> Texture
+	{
+		let pixels = vec![0; wh.0 as usize * wh.1 as usize];
+		let texture = base::Display::texture(self, wh, &VFrame(pixels));
+
+		self.backend.targets.push(RenderTarget {
+			texture: texture.0, wh, camera, shapes: None,
+		});
+		texture
+	}
+
+	/// Change the camera a render target is drawn from.
+	pub fn set_render_target_camera(&mut self, target: &Texture,
+		camera: Camera) -> Result<(), Error>
+	{
+		self.render_target(target)?.camera = camera;
+		Ok(())
+	}
+
+	/// Choose which opaque and alpha shapes are drawn into a render
+	/// target, or all of them with `None` (the default).  GUI shapes are
+	/// never drawn into render targets, and dropped shapes are skipped.
+	pub fn set_render_target_shapes(&mut self, target: &Texture,
+		shapes: Option<&[&Shape]>) -> Result<(), Error>
+	{
+		let shapes = match shapes {
+			Some(shapes) => {
+				let mut selection = Selection {
+					opaque: vec![], alpha: vec![],
+				};
+
+				for shape in shapes.iter() {
+					self.shape_mut(shape)?;
+
+					match base::get_shape(shape) {
+						ShapeHandle::Opaque(x) => {
+							selection.opaque.push(x)
+						}
+						ShapeHandle::Alpha(x) => {
+							selection.alpha.push(x)
+						}
+						ShapeHandle::Gui(_) => {}
+					}
+				}
+
+				Some(selection)
+			}
+			None => None,
+		};
+
+		self.render_target(target)?.shapes = shapes;
+		Ok(())
+	}
+
+	// Get a render target by its texture.
+	fn render_target(&mut self, target: &Texture)
+		-> Result<&mut RenderTarget, Error>
+	{
+		self.textures.get(target.0)?;
+
+		self.backend.targets.iter_mut()
+			.find(|t| t.texture == target.0)
+			.ok_or(Error::InvalidHandle)
+	}
+
+	// Draw the render targets, forgetting any whose texture was dropped.
+	fn draw_targets(&mut self) {
+		let textures = &self.textures;
+		self.backend.targets.retain(|t| textures.get(t.texture).is_ok());
+
+		for i in 0..self.backend.targets.len() {
+			let (texture, wh, camera, shapes) = {
+				let t = &self.backend.targets[i];
+
+				(t.texture, t.wh, t.camera, t.shapes.clone())
+			};
+			let ar = wh.0 as f32 / wh.1 as f32;
+			let matrix = camera.transform()
+				.m(projection::transform(self.lens, ar).0);
+			let mut frame = Frame::new(wh);
+
+			frame.clear(self.color, self.reversed_z());
+			let screen = mem::replace(&mut self.backend.frame, frame);
+			self.draw_world(camera, matrix, Transform::IDENTITY,
+				(0, 0, wh.0, wh.1), shapes.as_ref());
+			let frame = mem::replace(&mut self.backend.frame, screen);
+
+			*self.textures.get(texture).unwrap().borrow_mut() =
+				Image { w: wh.0, h: wh.1, pixels: frame.pixels() };
+		}
+	}
+
+	/// Turn on shadows, cast by the opaque shapes from directional and spot
+	/// lights onto shapes made with the lit styles, or turn them off with
+	/// `None` (the default).  The OpenGL `Display` can't do this until
+	/// `asi_opengl` has framebuffer objects for the shadow maps.
+	pub fn set_shadows(&mut self, shadows: Option<Shadows>) {
+		self.backend.shadows = shadows;
+	}
+
+	/// Set whether a shape casts shadows (only opaque shapes can) and
+	/// whether it receives them (only lit shapes can).  Both are on by
+	/// default.
+	pub fn shape_shadows(&mut self, shape: &Shape, cast: bool,
+		receive: bool) -> Result<(), Error>
+	{
+		let shape = self.shape_mut(shape)?;
+
+		shape.cast_shadows = cast;
+		shape.receive_shadows = receive;
+		Ok(())
+	}
+
+	// Draw a shadow map for each light, from the shapes that cast shadows.
+	fn shadow_maps(&self) -> Vec<Option<ShadowMap>> {
+		let shadows = match self.backend.shadows {
+			Some(shadows) => shadows,
+			None => return vec![],
+		};
+		let casters: Vec<_> = self.opaque.iter()
+			.filter(|shape| shape.cast_shadows)
+			.map(|shape| (shape.transform, &shape.vertex_buffer[..],
+				shape.topology, &shape.fans[..]))
+			.collect();
+
+		self.lights.iter()
+			.map(|light| ShadowMap::new(light, &shadows, &casters))
+			.collect()
+	}
+
+	/// Set the post-processing passes run, in order, after the opaque and
+	/// alpha shapes are drawn, before the GUI.  The OpenGL `Display` can't
+	/// do this until `asi_opengl` has an offscreen framebuffer to run the
+	/// chain on.
+	pub fn set_post_process(&mut self, chain: &[PostProcess]) {
+		self.backend.post = chain.to_vec();
+	}
+}
+
+impl Backend for Software {
+	type Buffer = Rc<Vec<f32>>;
+	type Texture = Rc<RefCell<Image>>;
+	type Location = ();
+
+	fn buffer(&mut self, data: &[f32]) -> Rc<Vec<f32>> {
+		Rc::new(data.to_vec())
+	}
+
+	fn texture(&mut self, wh: (u16, u16), pixels: &[u32])
+		-> Rc<RefCell<Image>>
+	{
+		Rc::new(RefCell::new(Image {
+			w: wh.0, h: wh.1, pixels: pixels.to_vec(),
+		}))
+	}
+
+	fn set_texture(&mut self, texture: &Rc<RefCell<Image>>, wh: (u16, u16),
+		pixels: &[u32])
+	{
+		*texture.borrow_mut() = Image {
+			w: wh.0, h: wh.1, pixels: pixels.to_vec(),
+		};
+	}
+
+	fn material_maps(&mut self, vertices: &Rc<Vec<f32>>,
+		texcoords: &Rc<Vec<f32>>, normals: &Rc<Vec<f32>>,
+		topology: Topology, fans: &[(u32, u32)], normal_map: bool)
+		-> Result<Option<Rc<Vec<f32>>>, Error>
+	{
+		Ok(if normal_map {
+			Some(Rc::new(material::tangents(vertices, texcoords,
+				normals, topology, fans)))
+		} else {
+			None
+		})
+	}
+
+	fn wh(&self) -> (u16, u16) {
+		self.frame.wh()
+	}
+
+	fn resize(&mut self, wh: (u16, u16)) {
+		self.frame = Frame::new(wh);
+	}
+
+	fn begin(&mut self, color: (f32, f32, f32), reversed_z: bool)
+		-> Option<Input>
+	{
+		self.frame.clear(color, reversed_z);
+
+		// There's no window, so there's never any input.
+		None
+	}
+
+	fn pass(&mut self, pass: &Pass) {
+		self.pass = Some(pass.clone());
+	}
+
+	fn draw(&mut self, shape: &ShapeData<Software>) {
+		let pass = match self.pass {
+			Some(ref pass) => pass,
+			None => return,
+		};
+		let texture = shape.texture.as_ref().map(|t| t.borrow());
+		let maps: Vec<_> = shape.material.iter()
+			.flat_map(|m| m.maps.iter())
+			.map(|map| map.as_ref().map(|t| t.borrow()))
+			.collect();
+		let material = shape.material.as_ref().map(|m| {
+			let map = |i: usize| maps[i].as_deref();
+
+			(m.factors, [map(0), map(1), map(2), map(3)])
+		});
+
+		let mut texpos = None;
+		let mut acolor = None;
+		let mut normal = None;
+		for &(name, buffer) in STYLE_ATTRIBUTES[shape.style].iter() {
+			let buffer = shape.buffers.get(buffer).map(|b| &b[..]);
+
+			match name {
+				b"texpos\0" => texpos = buffer,
+				b"acolor\0" => acolor = buffer,
+				b"normal\0" => normal = buffer,
+				_ => {}
+			}
+		}
+
+		let uniforms = Uniforms {
+			models_tfm: shape.transform,
+			matrix: pass.camera,
+			squeeze: pass.squeeze,
+			fog: if shape.has_fog { Some(pass.fog) } else { None },
+			sky: pass.sky.as_ref(),
+			alpha: shape.alpha.unwrap_or(1.0),
+			color: shape.color.unwrap_or([1.0, 1.0, 1.0, 1.0]),
+			texture: texture.as_deref(),
+			depth_test: pass.depth_test,
+			reversed_z: pass.reversed_z,
+			viewport: Some(pass.viewport),
+			lights: &pass.lights,
+			eye: [pass.eye.x, pass.eye.y, pass.eye.z],
+			shadows: if shape.receive_shadows {
+				&self.shadow_maps
+			} else {
+				&[]
+			},
+			material,
+		};
+		let attributes = Attributes {
+			position: &shape.vertex_buffer[..],
+			texpos,
+			acolor,
+			normal,
+			tangent: shape.material.as_ref()
+				.and_then(|m| m.tangents.as_ref())
+				.map(|t| &t[..]),
+		};
+
+		raster::draw(&mut self.frame, shape.style, &uniforms, &attributes,
+			shape.topology, &shape.fans);
+	}
+
+	fn end(&mut self) {}
+
+	fn before_world(display: &mut Display<Software>) {
+		display.backend.shadow_maps = display.shadow_maps();
+		display.draw_targets();
+	}
+
+	fn after_world(display: &mut Display<Software>) {
+		let frame = &mut display.backend.frame;
+		let wh = frame.wh();
+
+		for pass in display.backend.post.iter() {
+			pass.apply(wh, frame.color_mut());
+		}
+	}
+}
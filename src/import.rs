@@ -0,0 +1,757 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Mesh importers for Wavefront OBJ and glTF 2.0 files.
+//!
+//! Both formats are Y-up, with +Z towards the viewer and counter-clockwise
+//! triangles facing forward.  Positions and normals are turned half a turn
+//! around the X axis, into this crate's Y-down space where the camera looks
+//! down +Z, which also keeps each triangle facing the same way.
+
+use adi_gpu_base as base;
+use adi_gpu_base::*;
+
+use display::{Backend, Display};
+use indexed::{self, Indices, Primitive};
+use json::Json;
+use Error;
+
+/// Mesh data loaded from a file, to be uploaded to a `Display`.
+pub struct Mesh {
+	/// Vertex positions, 4 floats each.
+	pub vertices: Vec<f32>,
+	/// The triangles, as 3 indices into the vertices each.
+	pub indices: Vec<u32>,
+	/// Texture coordinates, 4 floats per vertex.
+	pub texcoords: Option<Vec<f32>>,
+	/// Vertex colors, 4 floats per vertex.
+	pub colors: Option<Vec<f32>>,
+	/// Normals, 4 floats per vertex, for the lit styles.
+	pub normals: Option<Vec<f32>>,
+	/// The base color image, still encoded.
+	pub image: Option<EncodedImage>,
+}
+
+/// An image as it's stored in a file.  Decoding it is left to an image
+/// library, in `Mesh::upload()`.
+pub struct EncodedImage {
+	/// The MIME type, like "image/png".
+	pub mime_type: String,
+	/// The encoded image.
+	pub data: Vec<u8>,
+}
+
+/// The handles of an uploaded `Mesh`.  The texture coordinates and gradient
+/// can only be used with its model.
+pub struct MeshHandles {
+	pub model: Model,
+	pub texcoords: Option<TexCoords>,
+	pub gradient: Option<Gradient>,
+	pub texture: Option<Texture>,
+}
+
+impl Mesh {
+	/// Load a mesh from the text of a Wavefront OBJ file.  Vertex colors
+	/// (`v x y z r g b`) become a gradient.
+	pub fn from_obj(text: &str) -> Result<Mesh, Error> {
+		let mut positions: Vec<[f32; 4]> = vec![];
+		let mut colors: Vec<[f32; 4]> = vec![];
+		let mut uvs: Vec<[f32; 4]> = vec![];
+		let mut directions: Vec<[f32; 4]> = vec![];
+		let mut mesh = Mesh {
+			vertices: vec![],
+			indices: vec![],
+			texcoords: None,
+			colors: None,
+			normals: None,
+			image: None,
+		};
+		let mut texcoords = vec![];
+		let mut normals = vec![];
+		let mut corners = vec![];
+		let mut has_uvs = None;
+		let mut has_normals = None;
+		let mut has_colors = false;
+
+		for line in text.lines() {
+			let mut words = line.split_whitespace();
+			let values = |words: ::std::str::SplitWhitespace|
+				-> Result<Vec<f32>, Error>
+			{
+				words.map(|w| w.parse().map_err(|_|
+					Error::Import("Invalid number in OBJ")))
+					.collect()
+			};
+
+			match words.next() {
+				Some("v") => {
+					let v = values(words)?;
+					match v.len() {
+						3 | 4 => {
+							let w = v.get(3).cloned()
+								.unwrap_or(1.0);
+							positions.push(y_down([v[0],
+								v[1], v[2], w]));
+							colors.push([1.0; 4]);
+						}
+						6 => {
+							positions.push(y_down([v[0],
+								v[1], v[2], 1.0]));
+							colors.push([v[3], v[4],
+								v[5], 1.0]);
+							has_colors = true;
+						}
+						_ => return Err(Error::Import(
+							"OBJ vertex needs 3, 4 or 6 values")),
+					}
+				}
+				Some("vt") => {
+					let t = values(words)?;
+					if t.is_empty() || t.len() > 3 {
+						return Err(Error::Import("OBJ \
+							texture coordinate needs 1 \
+							to 3 values"));
+					}
+					// OBJ's v goes up, texture rows go down.
+					let v = t.get(1).cloned().unwrap_or(0.0);
+					uvs.push([t[0], 1.0 - v, 0.0, 1.0]);
+				}
+				Some("vn") => {
+					let n = values(words)?;
+					if n.len() != 3 {
+						return Err(Error::Import("OBJ \
+							normal needs 3 values"));
+					}
+					directions.push(y_down([n[0], n[1],
+						n[2], 0.0]));
+				}
+				Some("f") => {
+					let start = mesh.vertices.len() as u32 / 4;
+
+					for corner in words {
+						let mut parts = corner.split('/');
+						let v = obj_index(parts.next(),
+							positions.len())?
+							.ok_or(Error::Import("OBJ \
+								face is missing a \
+								vertex"))?;
+						let t = obj_index(parts.next(),
+							uvs.len())?;
+						let n = obj_index(parts.next(),
+							directions.len())?;
+
+						if *has_uvs.get_or_insert(t.is_some())
+							!= t.is_some()
+						{
+							return Err(Error::Import(
+								"Some OBJ faces are \
+								missing texture \
+								coordinates"));
+						}
+						if *has_normals
+							.get_or_insert(n.is_some())
+							!= n.is_some()
+						{
+							return Err(Error::Import(
+								"Some OBJ faces are \
+								missing normals"));
+						}
+
+						mesh.vertices.extend_from_slice(
+							&positions[v]);
+						corners.push(v);
+						if let Some(t) = t {
+							texcoords.extend_from_slice(
+								&uvs[t]);
+						}
+						if let Some(n) = n {
+							normals.extend_from_slice(
+								&directions[n]);
+						}
+					}
+
+					let end = mesh.vertices.len() as u32 / 4;
+					if end - start < 3 {
+						return Err(Error::Import("OBJ face \
+							needs at least 3 \
+							vertices"));
+					}
+					// Faces are fans.
+					for i in start + 1..end - 1 {
+						mesh.indices.extend_from_slice(
+							&[start, i, i + 1]);
+					}
+				}
+				// Groups, materials and smoothing are ignored.
+				_ => {}
+			}
+		}
+
+		if has_colors {
+			mesh.colors = Some(corners.iter()
+				.flat_map(|v| colors[*v].iter().cloned())
+				.collect());
+		}
+		if has_uvs == Some(true) {
+			mesh.texcoords = Some(texcoords);
+		}
+		if has_normals == Some(true) {
+			mesh.normals = Some(normals);
+		}
+
+		Ok(mesh)
+	}
+
+	/// Load the meshes from a glTF 2.0 file, either binary (`.glb`) or
+	/// JSON (`.gltf`) with its buffers and images embedded.  There's one
+	/// `Mesh` for each primitive of each mesh, with the base color image
+	/// of its material; node transforms and the rest of the materials are
+	/// ignored.
+	pub fn from_gltf(data: &[u8]) -> Result<Vec<Mesh>, Error> {
+		let (json, bin) = if data.starts_with(b"glTF") {
+			glb(data)?
+		} else {
+			(data, None)
+		};
+		let json = ::std::str::from_utf8(json)
+			.map_err(|_| Error::Import("glTF JSON isn't UTF-8"))?;
+		let json = Json::parse(json)?;
+
+		let mut buffers = vec![];
+		for buffer in json.get("buffers").as_array() {
+			buffers.push(match buffer.get("uri").as_str() {
+				Some(uri) => data_uri(uri)?.1,
+				None => bin.ok_or(Error::Import("glTF buffer \
+					has no data"))?.to_vec(),
+			});
+		}
+
+		let mut meshes = vec![];
+		for mesh in json.get("meshes").as_array() {
+			for primitive in mesh.get("primitives").as_array() {
+				meshes.push(gltf_primitive(&json, &buffers,
+					primitive)?);
+			}
+		}
+
+		Ok(meshes)
+	}
+
+	/// Upload the mesh to a `Display` as a model from `model_indexed()`, so
+	/// shapes made from it are drawn with one draw call, checking that the
+	/// texture coordinates, colors and normals match the vertices.
+	/// `decode` turns the image, if there is one, into its width and
+	/// height and pixels, or `None` if it can't.
+	pub fn upload<B, F>(&self, display: &mut Display<B>, decode: F)
+		-> Result<MeshHandles, Error>
+		where B: Backend,
+			F: FnOnce(&EncodedImage) -> Option<((u16, u16), VFrame)>
+	{
+		let vertex_count = self.vertices.len() / 4;
+
+		for attribute in [&self.texcoords, &self.colors, &self.normals]
+			.iter()
+		{
+			if let Some(ref data) = **attribute {
+				if data.len() / 4 != vertex_count {
+					return Err(Error::VertexCountMismatch);
+				}
+			}
+		}
+
+		// Decode first, so nothing is uploaded if it fails.
+		let image = match self.image {
+			Some(ref image) => Some(decode(image).ok_or(Error::Import(
+				"Couldn't decode the mesh's image"))?),
+			None => None,
+		};
+
+		let model = display.model_indexed(&self.vertices,
+			Indices::U32(&self.indices), Primitive::TriangleList)?;
+		if let Some(ref normals) = self.normals {
+			display.set_normals(&model, normals)?;
+		}
+		let texcoords = match self.texcoords {
+			Some(ref t) => Some(display.texcoords_indexed(&model, t)?),
+			None => None,
+		};
+		let gradient = match self.colors {
+			Some(ref c) => Some(display.gradient_indexed(&model, c)?),
+			None => None,
+		};
+
+		Ok(MeshHandles {
+			model,
+			texcoords,
+			gradient,
+			texture: image.map(|(wh, pixels)| {
+				base::Display::texture(display, wh, &pixels)
+			}),
+		})
+	}
+}
+
+// Turn a position or normal from Y-up to Y-down, half a turn around X.
+fn y_down(v: [f32; 4]) -> [f32; 4] {
+	[v[0], -v[1], -v[2], v[3]]
+}
+
+// Resolve a 1-based (or negative, relative) OBJ index.
+fn obj_index(index: Option<&str>, len: usize) -> Result<Option<usize>, Error> {
+	let index = match index {
+		Some(index) if !index.is_empty() => index,
+		_ => return Ok(None),
+	};
+	let index: isize = index.parse()
+		.map_err(|_| Error::Import("Invalid OBJ index"))?;
+	let index = if index < 0 { len as isize + index } else { index - 1 };
+
+	if index < 0 || index as usize >= len {
+		return Err(Error::Import("OBJ index out of range"));
+	}
+
+	Ok(Some(index as usize))
+}
+
+// Split a binary glTF file into its JSON and BIN chunks.
+fn glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>), Error> {
+	let mut json = None;
+	let mut bin = None;
+	let mut i = 12;
+
+	while i + 8 <= data.len() {
+		let length = u32_le(data, i) as usize;
+		let kind = u32_le(data, i + 4);
+		let chunk = (i + 8).checked_add(length)
+			.and_then(|end| data.get(i + 8..end))
+			.ok_or(Error::Import("glTF chunk is truncated"))?;
+
+		match kind {
+			0x4E4F534A => json = Some(chunk), // JSON
+			0x004E4942 => bin = Some(chunk), // BIN
+			_ => {}
+		}
+		i += 8 + length;
+	}
+
+	Ok((json.ok_or(Error::Import("glTF has no JSON chunk"))?, bin))
+}
+
+fn gltf_primitive(json: &Json, buffers: &[Vec<u8>], primitive: &Json)
+	-> Result<Mesh, Error>
+{
+	let attributes = primitive.get("attributes");
+	let position = attributes.get("POSITION").as_usize()?
+		.ok_or(Error::Import("glTF primitive has no positions"))?;
+	let (positions, _) = accessor(json, buffers, position)?;
+	let count = positions.len() / 3;
+
+	let order: Vec<u32> = match primitive.get("indices").as_usize()? {
+		Some(indices) => accessor(json, buffers, indices)?.0.iter()
+			.map(|i| *i as u32).collect(),
+		None => (0..count as u32).collect(),
+	};
+	if order.iter().any(|i| *i as usize >= count) {
+		return Err(Error::IndexOutOfRange);
+	}
+
+	// Triangles (4), triangle strips (5) and triangle fans (6).
+	let indices = match primitive.get("mode").as_usize()?.unwrap_or(4) {
+		4 => indexed::triangle_list(&Indices::U32(&order),
+			Primitive::TriangleList),
+		5 => indexed::triangle_list(&Indices::U32(&order),
+			Primitive::TriangleStrip),
+		6 => {
+			let mut list = vec![];
+			for i in 2..order.len() {
+				list.extend_from_slice(&[order[0],
+					order[i - 1], order[i]]);
+			}
+			list
+		}
+		_ => return Err(Error::Import("Unsupported glTF primitive \
+			mode")),
+	};
+
+	let attribute = |name: &str, fill: [f32; 4]|
+		-> Result<Option<Vec<f32>>, Error>
+	{
+		let index = match attributes.get(name).as_usize()? {
+			Some(index) => index,
+			None => return Ok(None),
+		};
+		let (values, components) = accessor(json, buffers, index)?;
+		if values.len() / components != count {
+			return Err(Error::VertexCountMismatch);
+		}
+
+		let mut out = Vec::with_capacity(count * 4);
+		for v in values.chunks(components) {
+			for c in 0..4 {
+				out.push(if c < components {
+					v[c] as f32
+				} else {
+					fill[c]
+				});
+			}
+		}
+
+		Ok(Some(out))
+	};
+	let turned = |data: Option<Vec<f32>>| data.map(|mut data| {
+		for v in data.chunks_mut(4) {
+			let turned = y_down([v[0], v[1], v[2], v[3]]);
+
+			v.copy_from_slice(&turned);
+		}
+		data
+	});
+
+	Ok(Mesh {
+		vertices: turned(attribute("POSITION", [0.0, 0.0, 0.0, 1.0])?)
+			.unwrap(),
+		indices,
+		texcoords: attribute("TEXCOORD_0", [0.0, 0.0, 0.0, 1.0])?,
+		colors: attribute("COLOR_0", [1.0, 1.0, 1.0, 1.0])?,
+		normals: turned(attribute("NORMAL", [0.0, 0.0, 0.0, 0.0])?),
+		image: gltf_image(json, buffers, primitive)?,
+	})
+}
+
+// Get the base color image of a primitive's material, if it has one.
+fn gltf_image(json: &Json, buffers: &[Vec<u8>], primitive: &Json)
+	-> Result<Option<EncodedImage>, Error>
+{
+	let material = match primitive.get("material").as_usize()? {
+		Some(material) => json.get("materials").at(material),
+		None => return Ok(None),
+	};
+	let texture = match material.get("pbrMetallicRoughness")
+		.get("baseColorTexture").get("index").as_usize()?
+	{
+		Some(texture) => texture,
+		None => return Ok(None),
+	};
+	let image = json.get("textures").at(texture).get("source").as_usize()?
+		.map(|image| json.get("images").at(image))
+		.ok_or(Error::Import("glTF texture has no image"))?;
+
+	if let Some(uri) = image.get("uri").as_str() {
+		let (mime_type, data) = data_uri(uri)?;
+
+		return Ok(Some(EncodedImage {
+			mime_type: mime_type.to_string(), data,
+		}));
+	}
+
+	let view = json.get("bufferViews")
+		.at(image.get("bufferView").as_usize()?
+			.ok_or(Error::Import("glTF image has no data"))?);
+	let buffer = buffers.get(view.get("buffer").as_usize()?.unwrap_or(0))
+		.ok_or(Error::Import("glTF buffer view has no buffer"))?;
+	let offset = view.get("byteOffset").as_usize()?.unwrap_or(0);
+	let length = view.get("byteLength").as_usize()?
+		.ok_or(Error::Import("glTF buffer view has no length"))?;
+	let data = offset.checked_add(length)
+		.and_then(|end| buffer.get(offset..end))
+		.ok_or(Error::Import("glTF buffer view is out of bounds"))?;
+	let mime_type = image.get("mimeType").as_str()
+		.ok_or(Error::Import("glTF image has no MIME type"))?;
+
+	Ok(Some(EncodedImage {
+		mime_type: mime_type.to_string(), data: data.to_vec(),
+	}))
+}
+
+// Read an accessor's values, and how many components each element has.
+fn accessor(json: &Json, buffers: &[Vec<u8>], index: usize)
+	-> Result<(Vec<f64>, usize), Error>
+{
+	let accessor = json.get("accessors").at(index);
+	let view = json.get("bufferViews")
+		.at(accessor.get("bufferView").as_usize()?
+			.ok_or(Error::Import("glTF accessor has no buffer \
+				view"))?);
+	let buffer = buffers.get(view.get("buffer").as_usize()?.unwrap_or(0))
+		.ok_or(Error::Import("glTF buffer view has no buffer"))?;
+
+	let components = match accessor.get("type").as_str() {
+		Some("SCALAR") => 1,
+		Some("VEC2") => 2,
+		Some("VEC3") => 3,
+		Some("VEC4") => 4,
+		_ => return Err(Error::Import("Unsupported glTF accessor \
+			type")),
+	};
+	let kind = accessor.get("componentType").as_usize()?.unwrap_or(0);
+	let size = match kind {
+		5120 | 5121 => 1, // BYTE, UNSIGNED_BYTE
+		5122 | 5123 => 2, // SHORT, UNSIGNED_SHORT
+		5125 | 5126 => 4, // UNSIGNED_INT, FLOAT
+		_ => return Err(Error::Import("Unsupported glTF component \
+			type")),
+	};
+	let normalized = match *accessor.get("normalized") {
+		Json::Bool(normalized) => normalized,
+		_ => false,
+	};
+	let count = accessor.get("count").as_usize()?
+		.ok_or(Error::Import("glTF accessor has no count"))?;
+	let stride = view.get("byteStride").as_usize()?
+		.unwrap_or(components * size);
+
+	if count == 0 {
+		return Ok((vec![], components));
+	}
+	// Elements can't overlap, which also keeps `count` from being larger
+	// than the buffer.
+	if stride < components * size {
+		return Err(Error::Import("glTF byte stride is too small"));
+	}
+
+	let offset = view.get("byteOffset").as_usize()?.unwrap_or(0)
+		.checked_add(accessor.get("byteOffset").as_usize()?.unwrap_or(0));
+	let end = offset.and_then(|offset| (count - 1).checked_mul(stride)?
+		.checked_add(offset)?
+		.checked_add(components * size));
+	let offset = match (offset, end) {
+		(Some(offset), Some(end)) if end <= buffer.len() => offset,
+		_ => return Err(Error::Import("glTF accessor is out of \
+			bounds")),
+	};
+
+	let capacity = count.checked_mul(components)
+		.ok_or(Error::Import("glTF accessor is out of bounds"))?;
+	let mut values = Vec::with_capacity(capacity);
+	for i in 0..count {
+		for c in 0..components {
+			let at = offset + i * stride + c * size;
+			let (value, max) = match kind {
+				5120 => (buffer[at] as i8 as f64, 127.0),
+				5121 => (buffer[at] as f64, 255.0),
+				5122 => (u16_le(buffer, at) as i16 as f64, 32767.0),
+				5123 => (u16_le(buffer, at) as f64, 65535.0),
+				5125 => (u32_le(buffer, at) as f64, 1.0),
+				_ => (f32::from_bits(u32_le(buffer, at)) as f64,
+					1.0),
+			};
+
+			values.push(if normalized {
+				(value / max).max(-1.0)
+			} else {
+				value
+			});
+		}
+	}
+
+	Ok((values, components))
+}
+
+// Decode a base64 `data:` URI, returning its MIME type and data.
+fn data_uri(uri: &str) -> Result<(&str, Vec<u8>), Error> {
+	if !uri.starts_with("data:") {
+		return Err(Error::Import("External glTF files aren't \
+			supported"));
+	}
+	let i = uri.find(";base64,")
+		.ok_or(Error::Import("glTF data URI isn't base64"))?;
+	let (mime_type, base64) = (&uri[5..i], &uri[i + 8..]);
+
+	let mut out = Vec::with_capacity(base64.len() / 4 * 3);
+	let mut bits = 0u32;
+	let mut count = 0;
+	for c in base64.bytes() {
+		let value = match c {
+			b'A'..=b'Z' => c - b'A',
+			b'a'..=b'z' => c - b'a' + 26,
+			b'0'..=b'9' => c - b'0' + 52,
+			b'+' => 62,
+			b'/' => 63,
+			b'=' => break,
+			_ => return Err(Error::Import("Invalid base64 in glTF \
+				data URI")),
+		};
+
+		bits = (bits << 6) | value as u32;
+		count += 6;
+		if count >= 8 {
+			count -= 8;
+			out.push((bits >> count) as u8);
+		}
+	}
+
+	Ok((mime_type, out))
+}
+
+fn u16_le(data: &[u8], i: usize) -> u16 {
+	data[i] as u16 | (data[i + 1] as u16) << 8
+}
+
+fn u32_le(data: &[u8], i: usize) -> u32 {
+	data[i] as u32 | (data[i + 1] as u32) << 8 | (data[i + 2] as u32) << 16
+		| (data[i + 3] as u32) << 24
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A triangle: 3 positions, then 3 16-bit indices.
+	const BUFFER: &str = "data:application/octet-stream;base64,\
+		AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA=";
+
+	// A glTF file with one triangle, with `primitive` and `top` added to
+	// the primitive and the top level.
+	fn gltf(count: usize, stride: usize, primitive: &str, top: &str)
+		-> String
+	{
+		format!(r#"{{
+			"buffers": [{{ "uri": "{}", "byteLength": 44 }}],
+			"bufferViews": [
+				{{ "buffer": 0, "byteLength": 36,
+					"byteStride": {} }},
+				{{ "buffer": 0, "byteOffset": 36,
+					"byteLength": 6 }}
+			],
+			"accessors": [
+				{{ "bufferView": 0, "componentType": 5126,
+					"count": {}, "type": "VEC3" }},
+				{{ "bufferView": 1, "componentType": 5123,
+					"count": 3, "type": "SCALAR" }}
+			],
+			"meshes": [{{ "primitives": [{{
+				"attributes": {{ "POSITION": 0 }},
+				"indices": 1 {}
+			}}] }}] {}
+		}}"#, BUFFER, stride, count, primitive, top)
+	}
+
+	// The triangle in both files, turned to Y-down.
+	const TRIANGLE: [f32; 12] = [
+		0.0, 0.0, 0.0, 1.0,
+		1.0, 0.0, 0.0, 1.0,
+		0.0, -1.0, 0.0, 1.0,
+	];
+
+	#[test]
+	fn obj_triangle() {
+		let mesh = Mesh::from_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+			vt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\n\
+			f 1/1/1 2/2/1 -1/3/1\n").unwrap();
+
+		assert_eq!(mesh.vertices, TRIANGLE.to_vec());
+		assert_eq!(mesh.indices, vec![0, 1, 2]);
+		// OBJ's v is flipped.
+		assert_eq!(mesh.texcoords.unwrap()[..4], [0.0, 1.0, 0.0, 1.0]);
+		assert_eq!(mesh.normals.unwrap()[..4], [0.0, 0.0, -1.0, 0.0]);
+		assert!(mesh.colors.is_none());
+	}
+
+	#[test]
+	fn obj_faces() {
+		let mesh = Mesh::from_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+			f 1 2 3 4\nf 1 2 3\n").unwrap();
+
+		assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6]);
+	}
+
+	#[test]
+	fn gltf_modes() {
+		let indices = |mode: &str| {
+			let primitive = format!(r#", "mode": {}"#, mode);
+			let gltf = gltf(3, 12, &primitive, "");
+
+			Mesh::from_gltf(gltf.as_bytes()).map(|m| m[0].indices.clone())
+		};
+
+		assert_eq!(indices("4"), Ok(vec![0, 1, 2]));
+		assert_eq!(indices("5"), Ok(vec![0, 1, 2]));
+		assert_eq!(indices("6"), Ok(vec![0, 1, 2]));
+		assert_eq!(indices("0"),
+			Err(Error::Import("Unsupported glTF primitive mode")));
+		assert_eq!(indices("-4"),
+			Err(Error::Import("Expected a whole number in JSON")));
+	}
+
+	#[test]
+	fn obj_colors() {
+		let mesh = Mesh::from_obj("v 0 0 0 1 0 0\nv 1 0 0\nv 0 1 0\n\
+			f 1 2 3\n").unwrap();
+
+		assert_eq!(mesh.colors.unwrap(), vec![1.0, 0.0, 0.0, 1.0,
+			1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+	}
+
+	#[test]
+	fn obj_errors() {
+		let error = |text| Mesh::from_obj(text).err().unwrap();
+
+		assert_eq!(error("v 0 0 0\nf 1 2 3\n"),
+			Error::Import("OBJ index out of range"));
+		assert_eq!(error("v 0 0 0\nv 1 0 0\nf 1 2\n"),
+			Error::Import("OBJ face needs at least 3 vertices"));
+		assert_eq!(error("v 0 zero 0\n"),
+			Error::Import("Invalid number in OBJ"));
+		assert_eq!(error("v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\n\
+			f 1/1 2/1 3/1\nf 1 2 3\n"),
+			Error::Import("Some OBJ faces are missing texture \
+				coordinates"));
+	}
+
+	#[test]
+	fn gltf_triangle() {
+		let meshes = Mesh::from_gltf(gltf(3, 12, "", "").as_bytes())
+			.unwrap();
+
+		assert_eq!(meshes.len(), 1);
+		assert_eq!(meshes[0].vertices, TRIANGLE.to_vec());
+		assert_eq!(meshes[0].indices, vec![0, 1, 2]);
+		assert!(meshes[0].texcoords.is_none());
+		assert!(meshes[0].image.is_none());
+	}
+
+	#[test]
+	fn gltf_image() {
+		let top = r#", "materials": [{ "pbrMetallicRoughness": {
+				"baseColorTexture": { "index": 0 } } }],
+			"textures": [{ "source": 0 }],
+			"images": [{ "uri": "data:image/png;base64,iVBORw==" }]"#;
+		let gltf = gltf(3, 12, r#", "material": 0"#, top);
+		let meshes = Mesh::from_gltf(gltf.as_bytes()).unwrap();
+		let image = meshes[0].image.as_ref().unwrap();
+
+		assert_eq!(image.mime_type, "image/png");
+		assert_eq!(image.data, b"\x89PNG");
+	}
+
+	#[test]
+	fn gltf_out_of_bounds() {
+		let error = |count, stride| {
+			Mesh::from_gltf(gltf(count, stride, "", "").as_bytes())
+				.err().unwrap()
+		};
+
+		assert_eq!(error(4, 12),
+			Error::Import("glTF accessor is out of bounds"));
+		assert_eq!(error(::std::usize::MAX, 12),
+			Error::Import("glTF accessor is out of bounds"));
+		assert_eq!(error(3, 0),
+			Error::Import("glTF byte stride is too small"));
+	}
+
+	#[test]
+	fn base64() {
+		let (mime_type, data) =
+			data_uri("data:text/plain;base64,aGVsbG8=").unwrap();
+
+		assert_eq!(mime_type, "text/plain");
+		assert_eq!(data, b"hello");
+		assert_eq!(data_uri("data:;base64,aGV*").err(),
+			Some(Error::Import("Invalid base64 in glTF data URI")));
+		assert_eq!(data_uri("file.bin").err(),
+			Some(Error::Import("External glTF files aren't \
+				supported")));
+	}
+}
@@ -0,0 +1,121 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Indexed geometry for `Display::model_indexed()`.
+//!
+//! `asi_opengl` has no indexed draw call (no element buffers), so indices are
+//! expanded into a triangle list once, when uploading, and each shape is
+//! still drawn with a single draw call.
+
+use Error;
+
+/// Vertex indices, 16 or 32 bits each.
+pub enum Indices<'a> {
+	U16(&'a [u16]),
+	U32(&'a [u32]),
+}
+
+impl<'a> Indices<'a> {
+	fn get(&self, i: usize) -> u32 {
+		match *self {
+			Indices::U16(indices) => indices[i] as u32,
+			Indices::U32(indices) => indices[i],
+		}
+	}
+
+	fn len(&self) -> usize {
+		match *self {
+			Indices::U16(indices) => indices.len(),
+			Indices::U32(indices) => indices.len(),
+		}
+	}
+}
+
+/// How indexed vertices are assembled into triangles.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Primitive {
+	/// Every 3 indices make a triangle.
+	TriangleList,
+	/// Every index after the first 2 makes a triangle with the 2 before it.
+	TriangleStrip,
+}
+
+// Convert indices to a triangle list, skipping degenerate strip triangles.
+pub fn triangle_list(indices: &Indices, primitive: Primitive) -> Vec<u32> {
+	let mut list = vec![];
+
+	match primitive {
+		Primitive::TriangleList => {
+			for i in 0..indices.len() / 3 * 3 {
+				list.push(indices.get(i));
+			}
+		}
+		Primitive::TriangleStrip => {
+			for i in 2..indices.len() {
+				let (a, b, c) = (indices.get(i - 2),
+					indices.get(i - 1), indices.get(i));
+
+				if a == b || b == c || c == a {
+					continue;
+				}
+
+				// Every other triangle is flipped to keep the winding.
+				if i % 2 == 0 {
+					list.extend_from_slice(&[a, b, c]);
+				} else {
+					list.extend_from_slice(&[b, a, c]);
+				}
+			}
+		}
+	}
+
+	list
+}
+
+// Look up a 4 float per vertex attribute for each index in a triangle list.
+pub fn expand(data: &[f32], list: &[u32]) -> Result<Vec<f32>, Error> {
+	let mut out = Vec::with_capacity(list.len() * 4);
+
+	for i in list.iter() {
+		let i = *i as usize * 4;
+		let v = data.get(i..i + 4).ok_or(Error::IndexOutOfRange)?;
+
+		out.extend_from_slice(v);
+	}
+
+	Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn list() {
+		let indices = Indices::U16(&[0, 1, 2, 2, 3, 0, 4]);
+
+		assert_eq!(triangle_list(&indices, Primitive::TriangleList),
+			vec![0, 1, 2, 2, 3, 0]);
+	}
+
+	#[test]
+	fn strip() {
+		// The second triangle is flipped, and the degenerate ones
+		// joining the two strips are skipped.
+		let indices = Indices::U32(&[0, 1, 2, 3, 3, 4, 4, 5, 6]);
+
+		assert_eq!(triangle_list(&indices, Primitive::TriangleStrip),
+			vec![0, 1, 2, 2, 1, 3, 4, 5, 6]);
+	}
+
+	#[test]
+	fn out_of_range() {
+		let data = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+
+		assert_eq!(expand(&data, &[1, 0]),
+			Ok(vec![4.0, 5.0, 6.0, 7.0, 0.0, 1.0, 2.0, 3.0]));
+		assert_eq!(expand(&data, &[2]), Err(Error::IndexOutOfRange));
+	}
+}
@@ -0,0 +1,324 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Just enough JSON to read glTF files.
+
+use Error;
+
+pub enum Json {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(String, Json)>),
+}
+
+// Returned when looking up something that isn't there.
+static NULL: Json = Json::Null;
+
+// How deeply arrays and objects can nest, so the parser can't overflow the
+// stack.  glTF files don't go deeper than a few levels.
+const MAX_DEPTH: usize = 64;
+
+impl Json {
+	/// Parse a JSON document.
+	pub fn parse(text: &str) -> Result<Json, Error> {
+		let mut parser = Parser { text: text.as_bytes(), i: 0, depth: 0 };
+		let json = parser.value()?;
+
+		parser.space();
+		if parser.i != parser.text.len() {
+			return Err(Error::Import("Trailing characters after JSON"));
+		}
+
+		Ok(json)
+	}
+
+	/// Look up a member of an object, `Null` if missing.
+	pub fn get(&self, key: &str) -> &Json {
+		match *self {
+			Json::Object(ref members) => members.iter()
+				.find(|m| m.0 == key)
+				.map(|m| &m.1)
+				.unwrap_or(&NULL),
+			_ => &NULL,
+		}
+	}
+
+	/// Look up an element of an array, `Null` if missing.
+	pub fn at(&self, index: usize) -> &Json {
+		match *self {
+			Json::Array(ref elements) => elements.get(index)
+				.unwrap_or(&NULL),
+			_ => &NULL,
+		}
+	}
+
+	pub fn as_f64(&self) -> Option<f64> {
+		match *self {
+			Json::Number(n) => Some(n),
+			_ => None,
+		}
+	}
+
+	/// Get a count or index, `None` if it isn't a number.  Fails if it's
+	/// negative or not a whole number.
+	pub fn as_usize(&self) -> Result<Option<usize>, Error> {
+		match self.as_f64() {
+			Some(n) if n < 0.0 || n.fract() != 0.0 => Err(Error::Import(
+				"Expected a whole number in JSON")),
+			n => Ok(n.map(|n| n as usize)),
+		}
+	}
+
+	pub fn as_str(&self) -> Option<&str> {
+		match *self {
+			Json::String(ref s) => Some(s),
+			_ => None,
+		}
+	}
+
+	pub fn as_array(&self) -> &[Json] {
+		match *self {
+			Json::Array(ref elements) => elements,
+			_ => &[],
+		}
+	}
+}
+
+struct Parser<'a> {
+	text: &'a [u8],
+	i: usize,
+	depth: usize, // How many arrays and objects `i` is inside of.
+}
+
+impl<'a> Parser<'a> {
+	fn space(&mut self) {
+		while self.i < self.text.len()
+			&& (self.text[self.i] as char).is_whitespace()
+		{
+			self.i += 1;
+		}
+	}
+
+	fn peek(&mut self) -> Option<u8> {
+		self.space();
+		self.text.get(self.i).cloned()
+	}
+
+	fn expect(&mut self, c: u8) -> Result<(), Error> {
+		if self.peek() == Some(c) {
+			self.i += 1;
+			Ok(())
+		} else {
+			Err(Error::Import("Unexpected character in JSON"))
+		}
+	}
+
+	fn keyword(&mut self, word: &[u8], value: Json)
+		-> Result<Json, Error>
+	{
+		if self.text[self.i..].starts_with(word) {
+			self.i += word.len();
+			Ok(value)
+		} else {
+			Err(Error::Import("Unknown keyword in JSON"))
+		}
+	}
+
+	fn value(&mut self) -> Result<Json, Error> {
+		match self.peek() {
+			Some(b'{') => self.nested(Parser::object),
+			Some(b'[') => self.nested(Parser::array),
+			Some(b'"') => Ok(Json::String(self.string()?)),
+			Some(b't') => self.keyword(b"true", Json::Bool(true)),
+			Some(b'f') => self.keyword(b"false", Json::Bool(false)),
+			Some(b'n') => self.keyword(b"null", Json::Null),
+			Some(_) => self.number(),
+			None => Err(Error::Import("Unexpected end of JSON")),
+		}
+	}
+
+	// Parse an array or object, one level deeper.
+	fn nested<F>(&mut self, parse: F) -> Result<Json, Error>
+		where F: FnOnce(&mut Parser<'a>) -> Result<Json, Error>
+	{
+		if self.depth == MAX_DEPTH {
+			return Err(Error::Import("JSON is nested too deeply"));
+		}
+
+		self.depth += 1;
+		let json = parse(self);
+		self.depth -= 1;
+		json
+	}
+
+	fn object(&mut self) -> Result<Json, Error> {
+		let mut members = vec![];
+
+		self.expect(b'{')?;
+		if self.peek() == Some(b'}') {
+			self.i += 1;
+			return Ok(Json::Object(members));
+		}
+		loop {
+			self.space();
+			let key = self.string()?;
+			self.expect(b':')?;
+			members.push((key, self.value()?));
+
+			match self.peek() {
+				Some(b',') => self.i += 1,
+				Some(b'}') => { self.i += 1; break }
+				_ => return Err(Error::Import("Expected , or } in \
+					JSON object")),
+			}
+		}
+
+		Ok(Json::Object(members))
+	}
+
+	fn array(&mut self) -> Result<Json, Error> {
+		let mut elements = vec![];
+
+		self.expect(b'[')?;
+		if self.peek() == Some(b']') {
+			self.i += 1;
+			return Ok(Json::Array(elements));
+		}
+		loop {
+			elements.push(self.value()?);
+
+			match self.peek() {
+				Some(b',') => self.i += 1,
+				Some(b']') => { self.i += 1; break }
+				_ => return Err(Error::Import("Expected , or ] in \
+					JSON array")),
+			}
+		}
+
+		Ok(Json::Array(elements))
+	}
+
+	fn string(&mut self) -> Result<String, Error> {
+		self.expect(b'"')?;
+
+		let mut bytes = vec![];
+		loop {
+			let c = *self.text.get(self.i)
+				.ok_or(Error::Import("Unterminated JSON string"))?;
+			self.i += 1;
+
+			match c {
+				b'"' => break,
+				b'\\' => {
+					let e = *self.text.get(self.i)
+						.ok_or(Error::Import("Unterminated JSON string"))?;
+					self.i += 1;
+
+					let c = match e {
+						b'n' => '\n',
+						b't' => '\t',
+						b'r' => '\r',
+						b'b' => '\u{8}',
+						b'f' => '\u{c}',
+						b'u' => self.unicode()?,
+						e => e as char,
+					};
+					let mut buf = [0; 4];
+					bytes.extend_from_slice(
+						c.encode_utf8(&mut buf).as_bytes());
+				}
+				c => bytes.push(c),
+			}
+		}
+
+		String::from_utf8(bytes)
+			.map_err(|_| Error::Import("Invalid UTF-8 in JSON"))
+	}
+
+	fn unicode(&mut self) -> Result<char, Error> {
+		let hex = self.text.get(self.i..self.i + 4)
+			.ok_or(Error::Import("Unterminated JSON string"))?;
+		let hex = ::std::str::from_utf8(hex)
+			.map_err(|_| Error::Import("Invalid \\u escape in JSON"))?;
+		let code = u32::from_str_radix(hex, 16)
+			.map_err(|_| Error::Import("Invalid \\u escape in JSON"))?;
+
+		self.i += 4;
+		Ok(::std::char::from_u32(code).unwrap_or('\u{FFFD}'))
+	}
+
+	fn number(&mut self) -> Result<Json, Error> {
+		let start = self.i;
+
+		while self.i < self.text.len() && matches!(self.text[self.i],
+			b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')
+		{
+			self.i += 1;
+		}
+
+		::std::str::from_utf8(&self.text[start..self.i]).ok()
+			.and_then(|n| n.parse().ok())
+			.map(Json::Number)
+			.ok_or(Error::Import("Invalid number in JSON"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn values() {
+		let json = Json::parse(r#" { "a": [1, -2.5e1, true, null],
+			"b": { "c": "d\né\"" } } "#).unwrap();
+
+		assert_eq!(json.get("a").at(0).as_usize(), Ok(Some(1)));
+		assert_eq!(json.get("a").at(1).as_usize(),
+			Err(Error::Import("Expected a whole number in JSON")));
+		assert_eq!(json.get("b").as_usize(), Ok(None));
+		assert_eq!(json.get("a").at(1).as_f64(), Some(-25.0));
+		match *json.get("a").at(2) {
+			Json::Bool(true) => {}
+			_ => panic!("Expected true"),
+		}
+		match *json.get("a").at(3) {
+			Json::Null => {}
+			_ => panic!("Expected null"),
+		}
+		assert_eq!(json.get("b").get("c").as_str(), Some("d\né\""));
+		assert_eq!(json.get("a").as_array().len(), 4);
+	}
+
+	#[test]
+	fn missing() {
+		let json = Json::parse("{}").unwrap();
+
+		assert_eq!(json.get("a").get("b").at(0).as_f64(), None);
+		assert!(json.get("a").as_array().is_empty());
+	}
+
+	#[test]
+	fn errors() {
+		let error = |text| Json::parse(text).err().unwrap();
+
+		assert_eq!(error("[1, 2"), Error::Import("Expected , or ] in \
+			JSON array"));
+		assert_eq!(error(r#"{"a" 1}"#),
+			Error::Import("Unexpected character in JSON"));
+		assert_eq!(error(r#""abc"#),
+			Error::Import("Unterminated JSON string"));
+		assert_eq!(error("nul"), Error::Import("Unknown keyword in JSON"));
+		assert_eq!(error("1 2"),
+			Error::Import("Trailing characters after JSON"));
+		assert_eq!(error("-"), Error::Import("Invalid number in JSON"));
+		assert_eq!(error(&"[".repeat(200000)),
+			Error::Import("JSON is nested too deeply"));
+		assert!(Json::parse(&format!("{}{}", "[".repeat(MAX_DEPTH),
+			"]".repeat(MAX_DEPTH))).is_ok());
+	}
+}
@@ -4,10 +4,44 @@
 // https://www.boost.org/LICENSE_1_0.txt)
 
 //! OpenGL implementation for adi_gpu.
+//!
+//! Some features need parts of OpenGL that `asi_opengl` doesn't wrap yet, so
+//! only `Headless` has them:
+//!
+//! - Screenshots, which need `glReadPixels()`.
+//! - Render targets, which need framebuffer objects.
+//! - Post-processing, which needs an offscreen framebuffer.  `Headless` runs
+//!   the chain on the CPU.
+//! - Shadows, which need framebuffer objects for the shadow maps.
+//! - PBR textures besides the base color, which need more than one texture
+//!   unit.
 
 extern crate asi_opengl;
 extern crate adi_gpu_base;
 
+mod camera;
+mod custom;
+mod display;
+mod error;
+mod fog;
+mod handle;
+mod headless;
+mod import;
+mod indexed;
+mod json;
+mod light;
+mod material;
+mod math;
+mod post;
+mod projection;
+mod raster;
+mod resources;
+mod shadow;
+mod shapes;
+mod vector;
+mod viewport;
+mod watch;
+
 use std::mem;
 
 pub use base::Shape;
@@ -15,6 +49,21 @@ pub use base::Gradient;
 pub use base::Model;
 pub use base::TexCoords;
 pub use base::Texture;
+pub use camera::Camera;
+pub use custom::{CustomStyle, Uniform};
+pub use display::Display;
+pub use error::{Error, StyleReport};
+pub use fog::{Fog, FogMode};
+pub use headless::{Headless, Software};
+pub use import::{EncodedImage, Mesh, MeshHandles};
+pub use indexed::{Indices, Primitive};
+pub use light::{Light, MAX_LIGHTS};
+pub use material::Material;
+pub use post::PostProcess;
+pub use projection::Projection;
+pub use shadow::Shadows;
+pub use vector::Path;
+pub use viewport::Viewport;
 
 use adi_gpu_base as base;
 use asi_opengl::{
@@ -22,6 +71,7 @@ use asi_opengl::{
 	Feature, Topology,
 };
 use adi_gpu_base::*;
+use display::{Backend, Pass, ShapeData};
 
 const SHADER_SOLID_FRAG: &'static [u8] = include_bytes!("shaders/solid-frag.glsl");
 const SHADER_SOLID_VERT: &'static [u8] = include_bytes!("shaders/solid-vert.glsl");
@@ -33,6 +83,17 @@ const SHADER_FADED_VERT: &'static [u8] = include_bytes!("shaders/faded-vert.glsl
 const SHADER_TINTED_FRAG: &'static [u8] = include_bytes!("shaders/tinted-frag.glsl");
 const SHADER_COMPLEX_VERT: &'static [u8] = include_bytes!("shaders/complex-vert.glsl");
 const SHADER_COMPLEX_FRAG: &'static [u8] = include_bytes!("shaders/complex-frag.glsl");
+const SHADER_VG_VERT: &'static [u8] = include_bytes!("shaders/vg-vert.glsl");
+const SHADER_VG_FRAG: &'static [u8] = include_bytes!("shaders/vg-frag.glsl");
+const SHADER_LIT_SOLID_VERT: &'static [u8] = include_bytes!("shaders/lit-solid-vert.glsl");
+const SHADER_LIT_SOLID_FRAG: &'static [u8] = include_bytes!("shaders/lit-solid-frag.glsl");
+const SHADER_LIT_TEX_VERT: &'static [u8] = include_bytes!("shaders/lit-texture-vert.glsl");
+const SHADER_LIT_TEX_FRAG: &'static [u8] = include_bytes!("shaders/lit-texture-frag.glsl");
+const SHADER_LIT_TINTED_FRAG: &'static [u8] = include_bytes!("shaders/lit-tinted-frag.glsl");
+const SHADER_PBR_VERT: &'static [u8] = include_bytes!("shaders/pbr-vert.glsl");
+const SHADER_PBR_FRAG: &'static [u8] = include_bytes!("shaders/pbr-frag.glsl");
+const SHADER_FOG: &'static [u8] = include_bytes!("shaders/fog.glsl");
+const SHADER_LIGHT: &'static [u8] = include_bytes!("shaders/light.glsl");
 
 const STYLE_GRADIENT: usize = 0;
 const STYLE_TEXTURE: usize = 1;
@@ -40,108 +101,231 @@ const STYLE_FADED: usize = 2;
 const STYLE_TINTED: usize = 3;
 const STYLE_SOLID: usize = 4;
 const STYLE_COMPLEX: usize = 5;
+const STYLE_VG: usize = 6;
+const STYLE_LIT_SOLID: usize = 7;
+const STYLE_LIT_TEXTURE: usize = 8;
+const STYLE_LIT_TINTED: usize = 9;
+const STYLE_PBR: usize = 10;
+
+// Vertex and fragment shaders for each style.
+const STYLE_SHADERS: [(&'static [u8], &'static [u8]); 11] = [
+	(SHADER_GRADIENT_VERT, SHADER_GRADIENT_FRAG), // STYLE_GRADIENT
+	(SHADER_TEX_VERT, SHADER_TEX_FRAG), // STYLE_TEXTURE
+	(SHADER_FADED_VERT, SHADER_TEX_FRAG), // STYLE_FADED
+	(SHADER_TEX_VERT, SHADER_TINTED_FRAG), // STYLE_TINTED
+	(SHADER_SOLID_VERT, SHADER_SOLID_FRAG), // STYLE_SOLID
+	(SHADER_COMPLEX_VERT, SHADER_COMPLEX_FRAG), // STYLE_COMPLEX
+	(SHADER_VG_VERT, SHADER_VG_FRAG), // STYLE_VG
+	(SHADER_LIT_SOLID_VERT, SHADER_LIT_SOLID_FRAG), // STYLE_LIT_SOLID
+	(SHADER_LIT_TEX_VERT, SHADER_LIT_TEX_FRAG), // STYLE_LIT_TEXTURE
+	(SHADER_LIT_TEX_VERT, SHADER_LIT_TINTED_FRAG), // STYLE_LIT_TINTED
+	(SHADER_PBR_VERT, SHADER_PBR_FRAG), // STYLE_PBR
+];
+
+// Files each style's shaders were loaded from, for `watch_shaders()`.
+const STYLE_FILES: [(&'static str, &'static str); 11] = [
+	("gradient-vert.glsl", "gradient-frag.glsl"), // STYLE_GRADIENT
+	("texture-vert.glsl", "texture-frag.glsl"), // STYLE_TEXTURE
+	("faded-vert.glsl", "texture-frag.glsl"), // STYLE_FADED
+	("texture-vert.glsl", "tinted-frag.glsl"), // STYLE_TINTED
+	("solid-vert.glsl", "solid-frag.glsl"), // STYLE_SOLID
+	("complex-vert.glsl", "complex-frag.glsl"), // STYLE_COMPLEX
+	("vg-vert.glsl", "vg-frag.glsl"), // STYLE_VG
+	("lit-solid-vert.glsl", "lit-solid-frag.glsl"), // STYLE_LIT_SOLID
+	("lit-texture-vert.glsl", "lit-texture-frag.glsl"), // STYLE_LIT_TEXTURE
+	("lit-texture-vert.glsl", "lit-tinted-frag.glsl"), // STYLE_LIT_TINTED
+	("pbr-vert.glsl", "pbr-frag.glsl"), // STYLE_PBR
+];
+
+// The files `SHADER_FOG` and `SHADER_LIGHT` were loaded from, for
+// `watch_shaders()`.
+const FOG_FILE: &'static str = "fog.glsl";
+const LIGHT_FILE: &'static str = "light.glsl";
+
+// Whether each style's fragment shader uses the shared lights.
+const STYLE_LIGHTS: [bool; 11] = [
+	false, // STYLE_GRADIENT
+	false, // STYLE_TEXTURE
+	false, // STYLE_FADED
+	false, // STYLE_TINTED
+	false, // STYLE_SOLID
+	false, // STYLE_COMPLEX
+	false, // STYLE_VG
+	true, // STYLE_LIT_SOLID
+	true, // STYLE_LIT_TEXTURE
+	true, // STYLE_LIT_TINTED
+	true, // STYLE_PBR
+];
+
+// Names of the styles, for error messages.
+const STYLE_NAMES: [&'static str; 11] = [
+	"gradient", "texture", "faded", "tinted", "solid", "complex", "vector",
+	"lit solid", "lit texture", "lit tinted", "pbr",
+];
+
+// Uniforms that every style's shaders should have.
+const COMMON_UNIFORMS: [&'static [u8]; 9] = [
+	b"models_tfm\0", b"has_camera\0", b"matrix\0", b"squeeze\0",
+	b"has_fog\0", b"fog\0", b"range\0", b"viewport\0", b"eye\0",
+];
+
+// Other uniforms that each style's shaders should have.
+const STYLE_UNIFORMS: [&'static [&'static [u8]]; 11] = [
+	&[], // STYLE_GRADIENT
+	&[], // STYLE_TEXTURE
+	&[b"alpha\0"], // STYLE_FADED
+	&[b"color\0"], // STYLE_TINTED
+	&[b"color\0"], // STYLE_SOLID
+	&[], // STYLE_COMPLEX
+	&[b"color\0"], // STYLE_VG
+	&[b"color\0", b"light_count\0"], // STYLE_LIT_SOLID
+	&[b"light_count\0"], // STYLE_LIT_TEXTURE
+	&[b"color\0", b"light_count\0"], // STYLE_LIT_TINTED
+	&[b"color\0", b"material\0", b"emissive\0",
+		b"light_count\0"], // STYLE_PBR
+];
+
+// Which of `ShapeData::buffers` feeds each vertex attribute, for each style.
+const STYLE_ATTRIBUTES: [&'static [(&'static [u8], usize)]; 11] = [
+	&[(b"acolor\0", 0)], // STYLE_GRADIENT
+	&[(b"texpos\0", 0)], // STYLE_TEXTURE
+	&[(b"texpos\0", 0)], // STYLE_FADED
+	&[(b"texpos\0", 0)], // STYLE_TINTED
+	&[], // STYLE_SOLID
+	&[(b"texpos\0", 0), (b"acolor\0", 1)], // STYLE_COMPLEX
+	&[(b"texpos\0", 0)], // STYLE_VG
+	&[(b"normal\0", 0)], // STYLE_LIT_SOLID
+	&[(b"texpos\0", 0), (b"normal\0", 1)], // STYLE_LIT_TEXTURE
+	&[(b"texpos\0", 0), (b"normal\0", 1)], // STYLE_LIT_TINTED
+	&[(b"texpos\0", 0), (b"normal\0", 1)], // STYLE_PBR
+];
 
 struct Style {
 	shader: Program,
 	matrix_uniform: UniformData,
 	has_camera: UniformData,
 	camera_uniform: UniformData,
+	squeeze: UniformData,
 	has_fog: UniformData,
 	fog: UniformData,
 	range: UniformData,
+	sky: Vec<UniformData>, // Each color around the horizon.
+	viewport: UniformData,
 	alpha: UniformData,
 	color: UniformData,
+	material: UniformData,
+	emissive: UniformData,
+	eye: UniformData,
+	light_count: UniformData,
+	// `light_position`, `light_direction` and `light_color` for each light.
+	lights: Vec<(UniformData, UniformData, UniformData)>,
 	position: VertexData,
-	texpos: VertexData,
-	acolor: VertexData,
+	// Vertex attributes, and which of the shape's buffers they read.
+	attributes: Vec<(VertexData, usize)>,
+	name: String,
+	// Uniforms and attributes the shaders should have, but don't.
+	missing: Vec<String>,
 }
 
 impl Style {
-	// Create a new style.
-	fn new(context: &OpenGL, vert: &[u8], frag: &[u8]) -> Style {
+	// Create a new style.  `uniforms` are the uniforms it needs besides
+	// the common ones, which custom styles may leave out.  `asi_opengl`
+	// doesn't give compile or link logs, so any other missing uniform or
+	// attribute is an error, since it's there if the program built.
+	fn new(context: &OpenGL, name: String, vert: &[u8], frag: &[u8],
+		uniforms: &[&[u8]], attributes: &[(&[u8], usize)], custom: bool)
+		-> Result<Style, Error>
+	{
 		let shader = Program::new(context, vert, frag);
 		let matrix_uniform = shader.uniform(b"models_tfm\0");
 		let has_camera = shader.uniform(b"has_camera\0");
 		let camera_uniform = shader.uniform(b"matrix\0");
+		let squeeze = shader.uniform(b"squeeze\0");
 		let has_fog = shader.uniform(b"has_fog\0");
 		let fog = shader.uniform(b"fog\0");
 		let range = shader.uniform(b"range\0");
+		let sky = (0..fog::SKY_RING)
+			.map(|i| shader.uniform(&format!("sky[{}]\0", i)
+				.into_bytes()))
+			.collect();
+		let viewport = shader.uniform(b"viewport\0");
 		let alpha = shader.uniform(b"alpha\0");
 		let color = shader.uniform(b"color\0");
+		let material = shader.uniform(b"material\0");
+		let emissive = shader.uniform(b"emissive\0");
+		let eye = shader.uniform(b"eye\0");
+		let light_count = shader.uniform(b"light_count\0");
+		let lights = (0..MAX_LIGHTS).map(|i| {
+			let name = |n: &str| format!("{}[{}]\0", n, i)
+				.into_bytes();
+
+			(shader.uniform(&name("light_position")),
+				shader.uniform(&name("light_direction")),
+				shader.uniform(&name("light_color")))
+		}).collect();
 		let position = shader.vertex_data(b"position\0");
-		let texpos = shader.vertex_data(b"texpos\0");
-		let acolor = shader.vertex_data(b"acolor\0");
 
-		Style {
-			shader, matrix_uniform, has_camera, camera_uniform, fog,
-			range, position, texpos, alpha, has_fog, color, acolor,
+		let mut missing = vec![];
+		for uniform in COMMON_UNIFORMS.iter() {
+			if shader.uniform(uniform).is_none() {
+				missing.push(glsl_name(uniform));
+			}
+		}
+		let optional = if custom { missing.len() } else { 0 };
+		for uniform in uniforms.iter() {
+			if shader.uniform(uniform).is_none() {
+				missing.push(glsl_name(uniform));
+			}
+		}
+		if position.is_none() {
+			missing.push(glsl_name(b"position\0"));
+		}
+		for &(attribute, _) in attributes.iter() {
+			if shader.vertex_data(attribute).is_none() {
+				missing.push(glsl_name(attribute));
+			}
+		}
+		if missing.len() > optional {
+			return Err(Error::Shader(format!("The {} style's shaders \
+				failed to compile or link, or don't use {}", name,
+				missing[optional..].join(", "))));
 		}
-	}
-}
 
-struct ShapeData {
-	style: usize,
-	buffers: [Option<Buffer>; 2],
-	has_fog: bool,
-	alpha: Option<f32>,
-	color: Option<[f32; 4]>,
-	transform: Transform, // Transformation matrix.
-	texture: Option<asi_opengl::Texture>,
-	vertex_buffer: Buffer,
-	fans: Vec<(u32, u32)>,
-}
+		let attributes = attributes.iter()
+			.map(|&(name, buffer)| (shader.vertex_data(name), buffer))
+			.collect();
 
-impl ::adi_gpu_base::Point for ShapeData {
-	fn point(&self) -> Vec3 {
-		// Position vector at origin * object transform.
-		(self.transform.0 * vec4!(0f32, 0f32, 0f32, 1f32)).xyz()
+		Ok(Style {
+			shader, matrix_uniform, has_camera, camera_uniform,
+			squeeze, fog, range, sky, viewport, position, alpha, has_fog,
+			color,
+			material, emissive, eye, light_count, lights,
+			attributes, name, missing,
+		})
 	}
 }
 
-struct ModelData {
-	vertex_buffer: Buffer,
-	// TODO alot could be in base as duplicate
-	vertex_count: u32,
-	fans: Vec<(u32, u32)>,
-}
+// Get a uniform or attribute name without the nul terminator.
+fn glsl_name(name: &[u8]) -> String {
+	let name = name.split(|c| *c == 0).next().unwrap_or(name);
 
-struct TexcoordsData {
-	vertex_buffer: Buffer,
-	vertex_count: u32,
+	String::from_utf8_lossy(name).into_owned()
 }
 
-struct GradientData {
-	vertex_buffer: Buffer,
-	vertex_count: u32,
-}
-
-struct TextureData {
-	t: asi_opengl::Texture,
-}
-
-/// To render anything with adi_gpu, you have to make a `Display`
-pub struct Display {
+/// The OpenGL backend of `Display`, which draws into a window.
+pub struct Gl {
 	window: adi_gpu_base::Window,
 	context: OpenGL,
-	color: (f32, f32, f32),
-	opaque_ind: Vec<u32>,
-	alpha_ind: Vec<u32>,
-	opaque_vec: Vec<ShapeData>,
-	alpha_vec: Vec<ShapeData>,
-	gui_vec: Vec<ShapeData>,
-	models: Vec<ModelData>,
-	texcoords: Vec<TexcoordsData>,
-	gradients: Vec<GradientData>,
-	textures: Vec<TextureData>,
-	styles: [Style; 6],
-	xyz: Vec3,
-	rotate_xyz: Vec3,
-	ar: f32,
-	projection: Transform,
+	styles: Vec<Style>, // The built-in styles, then custom styles.
+	watch: Option<watch::Watch>, // Shader files to reload when changed.
+	reload_errors: Vec<Error>, // From reloading, until they're taken.
 }
 
-pub fn new(title: &str, icon: &afi::Video) -> Result<Box<Display>, &'static str>
-{
+/// Create a `Display` drawing into a new window.  Fails if OpenGL can't be
+/// found or isn't supported on this platform, or if a built-in style's
+/// shaders fail to build or lack a uniform or attribute (with the style's
+/// name; `asi_opengl` doesn't give the compile or link log).
+pub fn new(title: &str, icon: &afi::Video) -> Result<Box<Display>, Error> {
 	if let Some(tuple) = OpenGLBuilder::new() {
 		let (builder, v) = tuple;
 		let window = adi_gpu_base::Window::new(title, icon, Some(v));
@@ -156,23 +340,23 @@ pub fn new(title: &str, icon: &afi::Video) -> Result<Box<Display>, &'static str>
 				window
 			}
 			WindowConnection::Wayland => return Err(
-				"OpenGL support on Wayland is WIP"),
+				unsupported("OpenGL support on Wayland is WIP")),
 			WindowConnection::DirectFB => return Err(
-				"OpenGL support on DirectFB is WIP"),
+				unsupported("OpenGL support on DirectFB is WIP")),
 			WindowConnection::Android => return Err(
-				"OpenGL support on Android is WIP"),
+				unsupported("OpenGL support on Android is WIP")),
 			WindowConnection::IOS => return Err(
-				"OpenGL support on iOS is WIP"),
+				unsupported("OpenGL support on iOS is WIP")),
 			WindowConnection::AldaronsOS => return Err(
-				"AldaronsOS doesn't support OpenGL"),
+				unsupported("AldaronsOS doesn't support OpenGL")),
 			WindowConnection::Arduino => return Err(
-				"Arduino doesn't support OpenGL"),
+				unsupported("Arduino doesn't support OpenGL")),
 			WindowConnection::Switch => return Err(
-				"Nintendo Switch doesn't support OpenGL"),
+				unsupported("Nintendo Switch doesn't support OpenGL")),
 			WindowConnection::Web => return Err(
-				"WebGL support is WIP"),
+				unsupported("WebGL support is WIP")),
 			WindowConnection::NoOS => return Err(
-				"NoOS doesn't support OpenGL"),
+				unsupported("NoOS doesn't support OpenGL")),
 		});
 
 		// Set the settings.
@@ -182,557 +366,415 @@ pub fn new(title: &str, icon: &afi::Video) -> Result<Box<Display>, &'static str>
 		context.blend();
 
 		// Load shaders
-		let style_solid = Style::new(&context,
-			SHADER_SOLID_VERT, SHADER_SOLID_FRAG);
-		let style_gradient = Style::new(&context,
-			SHADER_GRADIENT_VERT, SHADER_GRADIENT_FRAG);
-		let style_texture = Style::new(&context,
-			SHADER_TEX_VERT, SHADER_TEX_FRAG);
-		let style_faded = Style::new(&context,
-			SHADER_FADED_VERT, SHADER_TEX_FRAG);
-		let style_tinted = Style::new(&context,
-			SHADER_TEX_VERT, SHADER_TINTED_FRAG);
-		let style_complex = Style::new(&context,
-			SHADER_COMPLEX_VERT, SHADER_COMPLEX_FRAG);
-
-		let wh = window.wh();
-		let ar = wh.0 as f32 / wh.1 as f32;
-
-		let projection = base::projection(ar, 0.5 * PI);
+		let mut styles = vec![];
+		for (i, &(vert, frag)) in STYLE_SHADERS.iter().enumerate() {
+			styles.push(Style::new(&context,
+				STYLE_NAMES[i].to_string(), vert,
+				&with_chunks(i, frag, SHADER_FOG, SHADER_LIGHT),
+				STYLE_UNIFORMS[i], STYLE_ATTRIBUTES[i], false)?);
+		}
 
 		// Adjust the viewport
+		let wh = window.wh();
 		context.viewport(wh.0, wh.1);
 
-		let mut display = ::Display {
-			window,
-			context,
-			color: (0.0, 0.0, 0.0),
-			alpha_ind: vec![],
-			opaque_ind: vec![],
-			alpha_vec: vec![],
-			opaque_vec: vec![],
-			gui_vec: vec![],
-			models: vec![],
-			texcoords: vec![],
-			gradients: vec![],
-			textures: vec![],
-			styles: [
-				style_gradient,
-				style_texture,
-				style_faded,
-				style_tinted,
-				style_solid,
-				style_complex,
-			],
-			xyz: vec3!(0.0, 0.0, 0.0),
-			rotate_xyz: vec3!(0.0, 0.0, 0.0),
-			ar,
-			projection,
-		};
-
-		use base::Display;
-		display.camera(vec3!(0.0, 0.0, 0.0), vec3!(0.0, 0.0, 0.0));
-
-		Ok(Box::new(display))
+		Ok(Box::new(Display::new(Gl {
+			window, context, styles, watch: None,
+			reload_errors: vec![],
+		})))
 	} else {
-		Err("Couldn't find OpenGL!")
+		Err(Error::OpenGL("Couldn't find OpenGL!".to_string()))
 	}
 }
 
-impl base::Display for Display {
-	fn color(&mut self, color: (f32, f32, f32)) {
-		self.color = color;
-		self.context.color(color.0, color.1, color.2);
+// Insert the shared GLSL into built-in style `i`'s fragment shader: the fog,
+// then the lights if it's lit.
+fn with_chunks(i: usize, frag: &[u8], fog: &[u8], light: &[u8]) -> Vec<u8> {
+	if STYLE_LIGHTS[i] {
+		insert(&insert(frag, light), fog)
+	} else {
+		insert(frag, fog)
 	}
+}
 
-	fn update(&mut self) -> Option<adi_gpu_base::Input> {
-		if let Some(input) = self.window.update() {
-			return Some(input);
-		}
-
-		// Update Window:
-		// TODO: This is copied pretty much from adi_gpu_vulkan.  Move
-		// to the base.
-
-		// Opaque & Alpha Shapes need a camera.
-		for i in (&self.styles).iter() {
-			i.has_camera.set_int1(1);
-		}
-
-		// Enable for 3D depth testing
-		self.context.enable(Feature::DepthTest);
-
-		// sort nearest
-		::adi_gpu_base::zsort(&mut self.opaque_ind, &self.opaque_vec,
-			true, self.xyz);
-		for shape in self.opaque_vec.iter() {
-			draw_shape(&self.styles[shape.style], shape);
-		}
+// Insert GLSL into a fragment shader, after its `precision` line (or its
+// first line, if it has none).
+fn insert(frag: &[u8], chunk: &[u8]) -> Vec<u8> {
+	let line = frag.windows(9).position(|w| w == b"precision")
+		.unwrap_or(0);
+	let at = frag[line..].iter().position(|&c| c == b'\n')
+		.map(|i| line + i + 1)
+		.unwrap_or(frag.len());
+	let mut out = Vec::with_capacity(frag.len() + chunk.len() + 2);
+
+	out.extend_from_slice(&frag[..at]);
+	out.extend_from_slice(b"\r\n");
+	out.extend_from_slice(chunk);
+	out.extend_from_slice(&frag[at..]);
+	out
+}
 
-		// sort farthest
-		::adi_gpu_base::zsort(&mut self.alpha_ind, &self.alpha_vec,
-			false, self.xyz);
-		for shape in self.alpha_vec.iter() {
-			draw_shape(&self.styles[shape.style], shape);
-		}
+// An error for a platform OpenGL isn't supported on.
+fn unsupported(message: &str) -> Error {
+	Error::Unsupported(message.to_string())
+}
 
-		// Disable Depth Testing for GUI
-		self.context.disable(Feature::DepthTest);
+/// Create a `Display` that renders into memory instead of a window, for
+/// machines without a screen or GPU.
+pub fn new_headless(wh: (u16, u16)) -> Box<Headless> {
+	Box::new(Display::new(Software::new(wh)))
+}
 
-		// Gui Elements don't want a camera.
-		for i in (&self.styles).iter() {
-			i.has_camera.set_int1(0);
-		}
+impl Display<Gl> {
+	/// List the standard uniforms each custom style's shaders don't have
+	/// (the built-in styles fail to build without theirs).  GLSL compilers
+	/// remove unused uniforms, so these may just be unused, or they may be
+	/// misspelled.
+	pub fn validate_styles(&self) -> Vec<StyleReport> {
+		self.backend.styles.iter()
+			.filter(|style| !style.missing.is_empty())
+			.map(|style| StyleReport {
+				style: style.name.clone(),
+				missing: style.missing.clone(),
+			})
+			.collect()
+	}
 
-		// No need to sort gui elements.
-		for shape in self.gui_vec.iter() {
-			draw_shape(&self.styles[shape.style], shape);
+	/// Reload the built-in styles' shaders from `dir` whenever their files
+	/// change, for tweaking them without rebuilding.  `None` watches this
+	/// crate's own `src/shaders`.  Files missing from `dir` keep the
+	/// built-in version, and changing `fog.glsl` rebuilds every style (or
+	/// `light.glsl`, every lit style).  If
+	/// a changed shader fails to build, the old one is kept and the error
+	/// is saved for `reload_errors()`.  Only works in debug builds.
+	pub fn watch_shaders(&mut self, dir: Option<&::std::path::Path>) {
+		if !cfg!(debug_assertions) {
+			return;
 		}
 
-		// end todo
+		let dir = match dir {
+			Some(dir) => dir.to_path_buf(),
+			None => concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders")
+				.into(),
+		};
 
-		self.context.update();
-		// Return None, there was no input, updated screen.
-		None
+		self.backend.watch = Some(watch::Watch::new(dir));
 	}
 
-	fn camera(&mut self, xyz: Vec3, rotate_xyz: Vec3) {
-		// Set Camera
-		self.xyz = xyz;
-		self.rotate_xyz = rotate_xyz;
-
-		// Write To Camera Uniforms.  TODO: only before use (not here).
-		// TODO this assignment copied from vulkan implementation.  Put
-		// in the base library.
-		let cam = Transform::IDENTITY
-			.t(vec3!()-self.xyz) // Move camera - TODO: negation operator?
-			.r(vec3!()-self.rotate_xyz) // Rotate camera - TODO: negation operator?
-			.m(self.projection.0); // Apply projection to camera
-
-		for i in (&self.styles).iter() {
-			i.camera_uniform.set_mat4(cam.into());
-		}
+	/// Take the errors from shaders that failed to build when they were
+	/// reloaded by `watch_shaders()`, oldest first.
+	pub fn reload_errors(&mut self) -> Vec<Error> {
+		mem::take(&mut self.backend.reload_errors)
 	}
 
-	fn model(&mut self, vertices: &[f32], fans: Vec<(u32, u32)>) -> Model {
-		// TODO most is duplicate from other implementation.
-		let index = self.models.len();
+	/// Make a style from your own GLSL (`#version 100`), for use with
+	/// `shape_custom()`.  Fails if the shaders don't compile or link, or
+	/// don't use `position` and each of `attributes`.  The standard
+	/// uniforms are set like they are for the built-in styles, if the
+	/// shaders have them: `models_tfm`, `has_camera`, `matrix`, `squeeze`
+	/// (applied after `matrix`, once the fog distance is found; with more
+	/// than one viewport, discard fragments whose z was outside of -w to w
+	/// before it, like the built-in styles do), `has_fog`, `fog`, `range`
+	/// (a `vec4`), `sky` (16 `vec4`s around the horizon, with an alpha of
+	/// 0 for no sky), `viewport` and `eye`, plus the `position` attribute.
+	/// `attributes` names the other vertex attributes, each 4 floats per
+	/// vertex.  Names may leave off the nul terminator.
+	pub fn custom_style(&mut self, vert: &[u8], frag: &[u8],
+		attributes: &[&[u8]]) -> Result<CustomStyle, Error>
+	{
+		let names: Vec<_> = attributes.iter()
+			.map(|name| custom::nul_terminated(name))
+			.collect();
+		let attributes: Vec<(&[u8], usize)> = names.iter()
+			.enumerate()
+			.map(|(i, name)| (&name[..], i))
+			.collect();
+
+		let styles = &mut self.backend.styles;
+		let name = format!("custom {}", styles.len());
+
+		styles.push(Style::new(&self.backend.context, name, vert, frag,
+			&[], &attributes, true)?);
+
+		// Styles are never dropped, so the generation is always 0.
+		Ok(CustomStyle(handle::pack(styles.len() - 1, self.id, 0)))
+	}
 
-		let buffer = Buffer::new(&self.context);
+	/// Create a shape drawn with a custom style.  `uniforms` sets the
+	/// shape's own uniforms by name, and `attributes` has a buffer, one
+	/// entry for each vertex of the model, for each of the style's
+	/// attributes, in the order they were named in `custom_style()`.
+	/// Uniform names may leave off the nul terminator.
+	pub fn shape_custom(&mut self, model: &Model, transform: Transform,
+		style: &CustomStyle, texture: Option<&Texture>,
+		uniforms: &[(&[u8], Uniform)], attributes: &[&[f32]],
+		blending: bool, fog: bool, camera: bool) -> Result<Shape, Error>
+	{
+		let vertex_count = self.models.get(model.0)?.vertex_count;
+		let (style, _) = handle::unpack(style.0, self.id)?;
+		let uniforms = {
+			let style_data = self.backend.styles.get(style)
+				.ok_or(Error::InvalidHandle)?;
+
+			if attributes.len() != style_data.attributes.len()
+				|| attributes.iter().any(|a| a.len() as u32 / 4
+					!= vertex_count)
+			{
+				return Err(Error::VertexCountMismatch);
+			}
 
-		let vertex_buffer = buffer;
-		vertex_buffer.set(vertices);
+			uniforms.iter()
+				.map(|&(name, value)| {
+					let name = custom::nul_terminated(name);
 
+					(style_data.shader.uniform(&name), value)
+				})
+				.collect()
+		};
+		let buffers = attributes.iter()
+			.map(|data| self.attribute_buffer(model, data))
+			.collect::<Result<_, _>>()?;
 
-		self.models.push(ModelData {
-			vertex_buffer, vertex_count: vertices.len() as u32 / 4,
-			fans
-		});
+		let mut shape = self.shape_data(style, model, buffers, texture,
+			transform, fog)?;
+		shape.uniforms = uniforms;
 
-		Model(index)
+		Ok(self.push_shape(shape, blending, fog, camera))
 	}
+}
 
-	fn fog(&mut self, fog: Option<(f32, f32)>) -> () {
-		let fogc = [self.color.0, self.color.1, self.color.2, 1.0];
-		let fogr = if let Some(fog) = fog {
-			[fog.0, fog.1]
-		} else {
-			[::std::f32::MAX, 0.0]
+impl Gl {
+	// Rebuild the built-in styles whose shader files changed.
+	fn reload_shaders(&mut self) {
+		let mut watch = match self.watch.take() {
+			Some(watch) => watch,
+			None => return,
 		};
 
-		for i in (&self.styles).iter() {
-			i.fog.set_vec4(&fogc);
-			i.range.set_vec2(&fogr);
-		}
-	}
-
-	fn texture(&mut self, wh: (u16,u16), graphic: &VFrame) -> Texture {
-		let (w, h) = wh;
-		let pixels = graphic.0.as_slice();
-
-		let t = self.context.texture();
-
-		t.set(w, h, pixels);
+		// Check every file, so each is only reported as modified once.
+		// Every style uses the fog, so they're all rebuilt when it changes,
+		// and the lit styles when the lights change.
+		let fog_modified = watch.modified(FOG_FILE);
+		let light_modified = watch.modified(LIGHT_FILE);
+		let modified: Vec<bool> = STYLE_FILES.iter()
+			.enumerate()
+			.map(|(i, &(vert, frag))| {
+				let vert = watch.modified(vert);
+				let frag = watch.modified(frag);
+
+				fog_modified || (light_modified && STYLE_LIGHTS[i])
+					|| vert || frag
+			})
+			.collect();
+		let fog = watch.read(FOG_FILE)
+			.unwrap_or_else(|| SHADER_FOG.to_vec());
+		let light = watch.read(LIGHT_FILE)
+			.unwrap_or_else(|| SHADER_LIGHT.to_vec());
+
+		for i in 0..STYLE_FILES.len() {
+			if !modified[i] {
+				continue;
+			}
 
-		let a = self.textures.len();
+			let (vert, frag) = STYLE_FILES[i];
+			let vert = watch.read(vert)
+				.unwrap_or_else(|| STYLE_SHADERS[i].0.to_vec());
+			let frag = watch.read(frag)
+				.unwrap_or_else(|| STYLE_SHADERS[i].1.to_vec());
+			let frag = with_chunks(i, &frag, &fog, &light);
 
-		self.textures.push(TextureData { t });
+			match Style::new(&self.context, STYLE_NAMES[i].to_string(),
+				&vert, &frag, STYLE_UNIFORMS[i], STYLE_ATTRIBUTES[i],
+				false)
+			{
+				Ok(style) => self.styles[i] = style,
+				Err(error) => self.reload_errors.push(error),
+			}
+		}
 
-		Texture(a, w, h)
+		self.watch = Some(watch);
 	}
+}
 
-	fn gradient(&mut self, colors: &[f32]) -> Gradient {
-		// TODO: A lot of duplication here from adi_gpu_vulkan.  Put in
-		// base.
-		let vertex_buffer = Buffer::new(&self.context);
-		vertex_buffer.set(colors);
-
-		let a = self.gradients.len();
+impl Backend for Gl {
+	type Buffer = Buffer;
+	type Texture = asi_opengl::Texture;
+	type Location = UniformData;
 
-		self.gradients.push(GradientData {
-			vertex_buffer,
-			vertex_count: colors.len() as u32 / 4,
-		});
+	fn buffer(&mut self, data: &[f32]) -> Buffer {
+		let buffer = Buffer::new(&self.context);
 
-		Gradient(a)
+		buffer.set(data);
+		buffer
 	}
 
-	fn texcoords(&mut self, texcoords: &[f32]) -> TexCoords {
-		// TODO: A lot of duplication here from adi_gpu_vulkan.  Put in
-		// base.
-		let vertex_buffer = Buffer::new(&self.context);
-		vertex_buffer.set(texcoords);
-
-		let a = self.texcoords.len();
-
-		self.texcoords.push(TexcoordsData {
-			vertex_buffer,
-			vertex_count: texcoords.len() as u32 / 4,
-		});
+	fn texture(&mut self, wh: (u16, u16), pixels: &[u32])
+		-> asi_opengl::Texture
+	{
+		let t = self.context.texture();
 
-		TexCoords(a)
+		t.set(wh.0, wh.1, pixels);
+		t
 	}
 
-	fn set_texture(&mut self, texture: &mut Texture, wh: (u16,u16),
-		graphic: &VFrame)
+	fn set_texture(&mut self, texture: &asi_opengl::Texture, wh: (u16, u16),
+		pixels: &[u32])
 	{
-		self.textures[texture.0].t.set(wh.0, wh.1,
-			graphic.0.as_slice());
+		texture.set(wh.0, wh.1, pixels);
 	}
 
-	#[inline(always)]
-	fn shape_solid(&mut self, model: &Model, transform: Transform,
-		color: [f32; 4], blending: bool, fog: bool, camera: bool)
-		-> Shape
+	fn material_maps(&mut self, _vertices: &Buffer, _texcoords: &Buffer,
+		_normals: &Buffer, _topology: Topology, _fans: &[(u32, u32)],
+		_normal_map: bool) -> Result<Option<Buffer>, Error>
 	{
-		let shape = ShapeData {
-			style: STYLE_SOLID,
-			buffers: [None, None],
-			has_fog: fog,
-			alpha: None,
-			color: Some(color),
-			texture: None,
-			vertex_buffer: self.models[model.0].vertex_buffer.clone(),
-			transform, // Transformation matrix.
-			fans: self.models[model.0].fans.clone(),
-		};
-
-		base::new_shape(if !camera && !fog {
-			let index = self.gui_vec.len() as u32;
-			self.gui_vec.push(shape);
-			base::ShapeHandle::Gui(index)
-		} else if blending {
-			let index = self.alpha_vec.len() as u32;
-			self.alpha_vec.push(shape);
-			self.alpha_ind.push(index);
-			base::ShapeHandle::Alpha(index)
-		} else {
-			let index = self.opaque_vec.len() as u32;
-			self.opaque_vec.push(shape);
-			self.opaque_ind.push(index);
-			base::ShapeHandle::Opaque(index)
-		})
+		Err(Error::Unsupported("asi_opengl can only bind the base \
+			color texture".to_string()))
 	}
 
-	#[inline(always)]
-	fn shape_gradient(&mut self, model: &Model, transform: Transform,
-		colors: Gradient, blending: bool, fog: bool, camera: bool)
-		-> Shape
-	{
-		// TODO: is copied from adi_gpu_vulkan, move to base
-		if self.models[model.0].vertex_count
-			!= self.gradients[colors.0].vertex_count
-		{
-			panic!("TexCoord length doesn't match gradient length");
-		}
-
-		let shape = ShapeData {
-			style: STYLE_GRADIENT,
-			buffers: [
-				Some(self.gradients[colors.0].vertex_buffer.clone()),
-				None
-			],
-			has_fog: fog,
-			alpha: None,
-			color: None,
-			texture: None,
-			vertex_buffer: self.models[model.0].vertex_buffer.clone(),
-			transform, // Transformation matrix.
-			fans: self.models[model.0].fans.clone(),
-		};
-
-		base::new_shape(if !camera && !fog {
-			let index = self.gui_vec.len() as u32;
-			self.gui_vec.push(shape);
-			base::ShapeHandle::Gui(index)
-		} else if blending {
-			let index = self.alpha_vec.len() as u32;
-			self.alpha_vec.push(shape);
-			self.alpha_ind.push(index);
-			base::ShapeHandle::Alpha(index)
-		} else {
-			let index = self.opaque_vec.len() as u32;
-			self.opaque_vec.push(shape);
-			self.opaque_ind.push(index);
-			base::ShapeHandle::Opaque(index)
-		})
+	fn wh(&self) -> (u16, u16) {
+		self.window.wh()
 	}
 
-	#[inline(always)]
-	fn shape_texture(&mut self, model: &Model, transform: Transform,
-		texture: &Texture, tc: TexCoords, blending: bool, fog: bool,
-		camera: bool) -> Shape
-	{
-		// TODO: from adi_gpu_vulkan, move to the base
-		if self.models[model.0].vertex_count
-			!= self.texcoords[tc.0].vertex_count
-		{
-			panic!("TexCoord length doesn't match vertex length");
-		}
-
-		let shape = ShapeData {
-			style: STYLE_TEXTURE,
-			buffers: [
-				Some(self.texcoords[tc.0].vertex_buffer.clone()),
-				None
-			],
-			has_fog: fog,
-			alpha: None,
-			color: None,
-			texture: Some(self.textures[texture.0].t.clone()),
-			vertex_buffer: self.models[model.0].vertex_buffer.clone(),
-			transform, // Transformation matrix.
-			fans: self.models[model.0].fans.clone(),
-		};
-
-		base::new_shape(if !camera && !fog {
-			let index = self.gui_vec.len() as u32;
-			self.gui_vec.push(shape);
-			base::ShapeHandle::Gui(index)
-		} else if blending {
-			let index = self.alpha_vec.len() as u32;
-			self.alpha_vec.push(shape);
-			self.alpha_ind.push(index);
-			base::ShapeHandle::Alpha(index)
-		} else {
-			let index = self.opaque_vec.len() as u32;
-			self.opaque_vec.push(shape);
-			self.opaque_ind.push(index);
-			base::ShapeHandle::Opaque(index)
-		})
+	fn resize(&mut self, wh: (u16, u16)) {
+		self.context.viewport(wh.0, wh.1);
 	}
 
-	#[inline(always)]
-	fn shape_faded(&mut self, model: &Model, transform: Transform,
-		texture: &Texture, tc: TexCoords, alpha: f32, fog: bool,
-		camera: bool) -> Shape
+	fn begin(&mut self, color: (f32, f32, f32), _reversed_z: bool)
+		-> Option<Input>
 	{
-		// TODO: from adi_gpu_vulkan, move to the base
-		if self.models[model.0].vertex_count
-			!= self.texcoords[tc.0].vertex_count
-		{
-			panic!("TexCoord length doesn't match vertex length");
+		if let Some(input) = self.window.update() {
+			return Some(input);
 		}
 
-		let shape = ShapeData {
-			style: STYLE_FADED,
-			buffers: [
-				Some(self.texcoords[tc.0].vertex_buffer.clone()),
-				None
-			],
-			has_fog: fog,
-			alpha: Some(alpha),
-			color: None,
-			texture: Some(self.textures[texture.0].t.clone()),
-			vertex_buffer: self.models[model.0].vertex_buffer.clone(),
-			transform, // Transformation matrix.
-			fans: self.models[model.0].fans.clone(),
-		};
-
-		base::new_shape(if !camera && !fog {
-			let index = self.gui_vec.len() as u32;
-			self.gui_vec.push(shape);
-			base::ShapeHandle::Gui(index)
-		} else {
-			let index = self.alpha_vec.len() as u32;
-			self.alpha_vec.push(shape);
-			self.alpha_ind.push(index);
-			base::ShapeHandle::Alpha(index)
-		})
+		self.context.color(color.0, color.1, color.2);
+		self.reload_shaders();
+		None
 	}
 
-	#[inline(always)]
-	fn shape_tinted(&mut self, model: &Model, transform: Transform,
-		texture: &Texture, tc: TexCoords, tint: [f32; 4], blending: bool,
-		fog: bool, camera: bool) -> Shape
-	{
-		// TODO: from adi_gpu_vulkan, move to the base
-		if self.models[model.0].vertex_count
-			!= self.texcoords[tc.0].vertex_count
-		{
-			panic!("TexCoord length doesn't match vertex length");
+	fn pass(&mut self, pass: &Pass) {
+		if pass.depth_test {
+			self.context.enable(Feature::DepthTest);
+		} else {
+			self.context.disable(Feature::DepthTest);
 		}
 
-		let shape = ShapeData {
-			style: STYLE_TINTED,
-			buffers: [
-				Some(self.texcoords[tc.0].vertex_buffer.clone()),
-				None,
-			],
-			has_fog: fog,
-			alpha: None,
-			color: Some(tint),
-			texture: Some(self.textures[texture.0].t.clone()),
-			vertex_buffer: self.models[model.0].vertex_buffer.clone(),
-			transform, // Transformation matrix.
-			fans: self.models[model.0].fans.clone(),
-		};
-
-		base::new_shape(if !camera && !fog {
-			let index = self.gui_vec.len() as u32;
-			self.gui_vec.push(shape);
-			base::ShapeHandle::Gui(index)
-		} else if blending {
-			let index = self.alpha_vec.len() as u32;
-			self.alpha_vec.push(shape);
-			self.alpha_ind.push(index);
-			base::ShapeHandle::Alpha(index)
+		// Only draw inside of a rectangle: left, right, bottom and top in
+		// pixels.  gl_FragCoord starts at the bottom left.
+		let h = self.window.wh().1 as f32;
+		let (x, y, w, vh) = (pass.viewport.0 as f32,
+			pass.viewport.1 as f32, pass.viewport.2 as f32,
+			pass.viewport.3 as f32);
+		let viewport = [x, h - y - vh, x + w, h - y];
+		let eye = [pass.eye.x, pass.eye.y, pass.eye.z, 1.0];
+		// The depth test is always LESS, so mirror reversed-Z depth back.
+		let squeeze = if pass.reversed_z {
+			pass.squeeze.m(mat4!(
+				1.0, 0.0, 0.0, 0.0,
+				0.0, 1.0, 0.0, 0.0,
+				0.0, 0.0, -1.0, 0.0,
+				0.0, 0.0, 0.0, 1.0
+			))
 		} else {
-			let index = self.opaque_vec.len() as u32;
-			self.opaque_vec.push(shape);
-			self.opaque_ind.push(index);
-			base::ShapeHandle::Opaque(index)
-		})
-	}
-
-	#[inline(always)]
-	fn shape_complex(&mut self, model: &Model, transform: Transform,
-		texture: &Texture, tc: TexCoords, tints: Gradient,
-		blending: bool, fog: bool, camera: bool) -> Shape
-	{
-		// TODO: from adi_gpu_vulkan, move to the base
-		if self.models[model.0].vertex_count
-			!= self.texcoords[tc.0].vertex_count
-		{
-			panic!("TexCoord length doesn't match vertex length");
-		}
+			pass.squeeze
+		};
 
-		// TODO: is copied from adi_gpu_vulkan, move to base
-		if self.models[model.0].vertex_count
-			!= self.gradients[tints.0].vertex_count
-		{
-			panic!("TexCoord length doesn't match gradient length");
-		}
+		for style in self.styles.iter() {
+			match pass.camera {
+				Some(camera) => {
+					style.has_camera.set_int1(1);
+					style.camera_uniform.set_mat4(camera.into());
+					style.squeeze.set_mat4(squeeze.into());
+				}
+				None => style.has_camera.set_int1(0),
+			}
+			style.viewport.set_vec4(&viewport);
+			style.fog.set_vec4(&(pass.fog.0));
+			style.range.set_vec4(&(pass.fog.1));
+			match pass.sky {
+				Some(ref sky) => {
+					for (color, u) in sky.iter().zip(&style.sky) {
+						u.set_vec4(color);
+					}
+				}
+				// An alpha of 0 turns the sky off.
+				None => style.sky[0].set_vec4(&[0.0; 4]),
+			}
 
-		let shape = ShapeData {
-			style: STYLE_COMPLEX,
-			buffers: [
-				Some(self.texcoords[tc.0].vertex_buffer.clone()),
-				Some(self.gradients[tints.0].vertex_buffer.clone()),
-			],
-			has_fog: fog,
-			alpha: None,
-			color: None,
-			texture: Some(self.textures[texture.0].t.clone()),
-			vertex_buffer: self.models[model.0].vertex_buffer.clone(),
-			transform, // Transformation matrix.
-			fans: self.models[model.0].fans.clone(),
-		};
+			style.eye.set_vec4(&eye);
 
-		base::new_shape(if !camera && !fog {
-			let index = self.gui_vec.len() as u32;
-			self.gui_vec.push(shape);
-			base::ShapeHandle::Gui(index)
-		} else if blending {
-			let index = self.alpha_vec.len() as u32;
-			self.alpha_vec.push(shape);
-			self.alpha_ind.push(index);
-			base::ShapeHandle::Alpha(index)
-		} else {
-			let index = self.opaque_vec.len() as u32;
-			self.opaque_vec.push(shape);
-			self.opaque_ind.push(index);
-			base::ShapeHandle::Opaque(index)
-		})
-	}
+			// Write the lights to the lit styles' uniforms.
+			if style.light_count.is_none() {
+				continue;
+			}
+			style.light_count.set_int1(pass.lights.len() as i32);
+			for (light, u) in pass.lights.iter().zip(&style.lights) {
+				let values = light.uniforms();
 
-	#[inline(always)]
-	fn drop_shape(&mut self, shape: &Shape) {
-		match get_shape(&shape) {
-			ShapeHandle::Opaque(x) => {
-				let index = self.opaque_ind.iter()
-					.position(|y| *y == x).unwrap();
-				self.opaque_ind.remove(index);
-			},
-			ShapeHandle::Alpha(x) => {
-				let index = self.alpha_ind.iter()
-					.position(|y| *y == x).unwrap();
-				self.alpha_ind.remove(index);
-			},
-			ShapeHandle::Gui(x) => {
-				// TODO: make it obvious that there's only meant
-				// to be 1 GUI object.
-				self.gui_vec.clear();
-			},
+				u.0.set_vec4(&values[0]);
+				u.1.set_vec4(&values[1]);
+				u.2.set_vec4(&values[2]);
+			}
 		}
 	}
 
-	fn transform(&mut self, shape: &Shape, transform: Transform) {
-		// TODO: put in base, some is copy from vulkan implementation.
-		match base::get_shape(shape) {
-			ShapeHandle::Opaque(x) => {
-				let x = x as usize; // for indexing
-				self.opaque_vec[x].transform = transform;
-			},
-			ShapeHandle::Alpha(x) => {
-				let x = x as usize; // for indexing
-				self.alpha_vec[x].transform = transform;
-			},
-			ShapeHandle::Gui(x) => {
-				let x = x as usize; // for indexing
-				self.gui_vec[x].transform = transform;
-			},
-		}
+	fn draw(&mut self, shape: &ShapeData<Gl>) {
+		draw_shape(&self.styles[shape.style], shape);
 	}
 
-	fn resize(&mut self, wh: (u16, u16)) -> () {
-		let xyz = self.xyz;
-		let rotate_xyz = self.rotate_xyz;
+	fn end(&mut self) {
+		self.context.update();
+	}
+}
 
-		self.ar = wh.0 as f32 / wh.1 as f32;
-		self.context.viewport(wh.0, wh.1);
+fn draw_shape(style: &Style, shape: &ShapeData<Gl>) {
+	style.matrix_uniform.set_mat4(shape.transform.into());
 
-		self.projection = ::base::projection(self.ar, 0.5 * PI);
-		self.camera(xyz, rotate_xyz);
+	// Set the vertex attributes from the shape's buffers.
+	for &(ref attribute, buffer) in style.attributes.iter() {
+		if !attribute.is_none() {
+			attribute.set(&shape.buffers[buffer]);
+		}
 	}
 
-	fn wh(&self) -> (u16, u16) {
-		self.window.wh()
+	// Set the custom style's uniforms.
+	for (uniform, value) in shape.uniforms.iter() {
+		if !uniform.is_none() {
+			value.set(uniform);
+		}
 	}
-}
 
-fn draw_shape(style: &Style, shape: &ShapeData) {
-	style.matrix_uniform.set_mat4(shape.transform.into());
-
-	if !style.texpos.is_none() {
-		// Set texpos for the program from the texpos buffer.
-		style.texpos.set(shape.buffers[0].as_ref().unwrap());
-		// Bind the texture
-		shape.texture.as_ref().unwrap().bind();
+	// Bind the texture
+	if let Some(ref texture) = shape.texture {
+		texture.bind();
 	}
 
-	if !style.acolor.is_none() {
-		// Set colors for the program from the color buffer.
-		// TODO: probably shouldn't be same buffer as texpos.
-		style.acolor.set(shape.buffers[0].as_ref().unwrap());
+	if let Some(alpha) = shape.alpha {
+		if !style.alpha.is_none() {
+			style.alpha.set_vec1(alpha);
+		}
 	}
 
-	if !style.alpha.is_none() {
-		style.alpha.set_vec1(shape.alpha.unwrap());
+	if let Some(ref color) = shape.color {
+		if !style.color.is_none() {
+			style.color.set_vec4(color);
+		}
 	}
 
-	if !style.color.is_none() {
-		style.color.set_vec4(&shape.color.unwrap());
+	if let Some(ref material) = shape.material {
+		if !style.material.is_none() {
+			let factors = material.factors;
+			let has_texture = if shape.texture.is_some() {
+				1.0
+			} else {
+				0.0
+			};
+			let emissive = factors.emissive;
+
+			style.material.set_vec4(&[factors.metallic,
+				factors.roughness, has_texture, 0.0]);
+			style.emissive.set_vec4(&[emissive[0], emissive[1],
+				emissive[2], 0.0]);
+		}
 	}
 
 	if shape.has_fog {
@@ -744,6 +786,6 @@ fn draw_shape(style: &Style, shape: &ShapeData) {
 	// Set vertices for the program from the vertex buffer.
 	style.position.set(&shape.vertex_buffer);
 	for i in shape.fans.iter() {
-		style.shader.draw_arrays(Topology::TriangleFan, i.0..i.1);
+		style.shader.draw_arrays(shape.topology, i.0..i.1);
 	}
 }
@@ -0,0 +1,230 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Lights for `Display::light()`, which light the lit styles with Blinn-Phong
+//! shading and the PBR style with Cook-Torrance.  `shade()` follows
+//! `src/shaders/light.glsl`.
+
+use adi_gpu_base::*;
+
+use math::{dot, length, normalize, sub};
+
+/// How many lights can be on at once.
+pub const MAX_LIGHTS: usize = 8;
+
+// How shiny lit shapes are, and how bright their highlights are.
+const SHININESS: f32 = 32.0;
+const SPECULAR: f32 = 0.5;
+
+/// A light, shining on shapes made with the lit styles.  Colors can be
+/// brighter than 1 for stronger lights.
+#[derive(Copy, Clone)]
+pub enum Light {
+	/// Light coming from everywhere, so nothing is completely dark.
+	Ambient {
+		color: [f32; 3],
+	},
+	/// Light shining in one `direction`, like the sun.
+	Directional {
+		direction: Vec3,
+		color: [f32; 3],
+	},
+	/// Light shining out from `position`, fading to nothing at `range`
+	/// (or never fading if `range` is 0).
+	Point {
+		position: Vec3,
+		color: [f32; 3],
+		range: f32,
+	},
+	/// A point light that only shines within `angle` (radians, less than a
+	/// quarter turn) of `direction`, softening over the outer edge of the
+	/// cone.
+	Spot {
+		position: Vec3,
+		direction: Vec3,
+		color: [f32; 3],
+		range: f32,
+		angle: f32,
+	},
+}
+
+impl Light {
+	// Get the `light_position`, `light_direction` and `light_color`
+	// uniforms.  The kind of light is in position's w, the cosine of the
+	// spot angle in direction's w, and the range in color's w.
+	pub(crate) fn uniforms(&self) -> [[f32; 4]; 3] {
+		match *self {
+			Light::Ambient { color } => [
+				[0.0, 0.0, 0.0, 0.0],
+				[0.0, 0.0, 0.0, 0.0],
+				[color[0], color[1], color[2], 0.0],
+			],
+			Light::Directional { direction, color } => [
+				[0.0, 0.0, 0.0, 1.0],
+				[direction.x, direction.y, direction.z, 0.0],
+				[color[0], color[1], color[2], 0.0],
+			],
+			Light::Point { position, color, range } => [
+				[position.x, position.y, position.z, 2.0],
+				[0.0, 0.0, 0.0, 0.0],
+				[color[0], color[1], color[2], range],
+			],
+			Light::Spot { position, direction, color, range, angle }
+				=> [
+				[position.x, position.y, position.z, 3.0],
+				[direction.x, direction.y, direction.z,
+					angle.cos()],
+				[color[0], color[1], color[2], range],
+			],
+		}
+	}
+}
+
+// Light `base` color at `position`, facing `normal`, seen from `eye`.
+// `visibility` is how much of each light isn't shadowed, 1 if it's missing.
+pub(crate) fn shade(lights: &[Light], visibility: &[f32], eye: [f32; 3],
+	position: [f32; 3], normal: [f32; 3], base: [f32; 3]) -> [f32; 3]
+{
+	let n = normalize(normal);
+	let v = normalize(sub(eye, position));
+	let mut total = [0.0; 3];
+
+	for (i, light) in lights.iter().enumerate() {
+		let (color, l, attenuation) = match incoming(light, position) {
+			Some(incoming) => incoming,
+			None => {
+				let color = light.uniforms()[2];
+				for j in 0..3 {
+					total[j] += color[j] * base[j];
+				}
+				continue;
+			}
+		};
+		let attenuation = attenuation
+			* visibility.get(i).cloned().unwrap_or(1.0);
+
+		let diffuse = dot(n, l).max(0.0);
+		let specular = if diffuse > 0.0 {
+			let h = normalize([l[0] + v[0], l[1] + v[1], l[2] + v[2]]);
+			dot(n, h).max(0.0).powf(SHININESS)
+		} else {
+			0.0
+		};
+
+		for j in 0..3 {
+			total[j] += color[j] * attenuation
+				* (diffuse * base[j] + SPECULAR * specular);
+		}
+	}
+
+	total
+}
+
+// Like `shade()`, but with the Cook-Torrance metallic-roughness model used by
+// glTF.  `occlusion` darkens the ambient light.  Follows
+// `src/shaders/pbr-frag.glsl`.
+pub(crate) fn shade_pbr(lights: &[Light], visibility: &[f32], eye: [f32; 3],
+	position: [f32; 3], normal: [f32; 3], base: [f32; 3], metallic: f32,
+	roughness: f32, occlusion: f32) -> [f32; 3]
+{
+	let n = normalize(normal);
+	let v = normalize(sub(eye, position));
+	let n_dot_v = dot(n, v).max(0.0001);
+	let a = roughness * roughness;
+	let a2 = a * a;
+	let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+	let f0 = [
+		0.04 + (base[0] - 0.04) * metallic,
+		0.04 + (base[1] - 0.04) * metallic,
+		0.04 + (base[2] - 0.04) * metallic,
+	];
+	let mut total = [0.0; 3];
+
+	for (i, light) in lights.iter().enumerate() {
+		let (color, l, attenuation) = match incoming(light, position) {
+			Some(incoming) => incoming,
+			None => {
+				let color = light.uniforms()[2];
+				for j in 0..3 {
+					total[j] += color[j] * base[j] * occlusion;
+				}
+				continue;
+			}
+		};
+		let attenuation = attenuation
+			* visibility.get(i).cloned().unwrap_or(1.0);
+
+		let n_dot_l = dot(n, l);
+		if n_dot_l <= 0.0 {
+			continue;
+		}
+
+		let h = normalize([l[0] + v[0], l[1] + v[1], l[2] + v[2]]);
+		let n_dot_h = dot(n, h).max(0.0);
+		let v_dot_h = dot(v, h).max(0.0);
+
+		// Distribution (GGX), geometry (Smith) and Fresnel (Schlick).
+		let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+		let d = a2 / (PI * d * d);
+		let g = n_dot_v / (n_dot_v * (1.0 - k) + k)
+			* n_dot_l / (n_dot_l * (1.0 - k) + k);
+		let fresnel = (1.0 - v_dot_h).powi(5);
+
+		for j in 0..3 {
+			let f = f0[j] + (1.0 - f0[j]) * fresnel;
+			let specular = d * g * f / (4.0 * n_dot_l * n_dot_v);
+			let diffuse = (1.0 - f) * (1.0 - metallic) * base[j] / PI;
+
+			total[j] += color[j] * attenuation * n_dot_l
+				* (diffuse + specular);
+		}
+	}
+
+	total
+}
+
+// Get a light's color, the direction towards it and how much of it reaches
+// `position`.  `None` for ambient light, which comes from everywhere.
+fn incoming(light: &Light, position: [f32; 3])
+	-> Option<([f32; 3], [f32; 3], f32)>
+{
+	let u = light.uniforms();
+	let (kind, color) = (u[0][3], [u[2][0], u[2][1], u[2][2]]);
+
+	if kind < 0.5 {
+		return None;
+	}
+
+	if kind < 1.5 {
+		return Some((color, normalize([-u[1][0], -u[1][1], -u[1][2]]),
+			1.0));
+	}
+
+	let to = sub([u[0][0], u[0][1], u[0][2]], position);
+	let distance = length(to);
+	let range = u[2][3];
+	let mut attenuation = 1.0;
+
+	if range > 0.0 {
+		let fade = (1.0 - distance / range).clamp(0.0, 1.0);
+		attenuation = fade * fade;
+	}
+	if kind > 2.5 {
+		let cone = u[1][3];
+		let theta = -dot(normalize(to),
+			normalize([u[1][0], u[1][1], u[1][2]]));
+
+		attenuation *= smoothstep(cone, cone + (1.0 - cone) * 0.1,
+			theta);
+	}
+
+	Some((color, normalize(to), attenuation))
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+	let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+
+	t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,149 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Physically-based materials for `Display::shape_pbr()`, following glTF's
+//! metallic-roughness materials.
+
+use adi_gpu_base::*;
+use asi_opengl::Topology;
+
+use math::{cross, dot, normalize, scale, sub};
+use raster;
+
+/// A physically-based material, with the same meaning as a glTF material.
+/// Each texture is multiplied by its factor, and all of them use the shape's
+/// texture coordinates.
+pub struct Material<'a> {
+	/// The base color (linear RGBA).
+	pub base_color: [f32; 4],
+	/// The base color texture.
+	pub base_color_texture: Option<&'a Texture>,
+	/// How metallic the surface is, 0 to 1.
+	pub metallic: f32,
+	/// How rough the surface is, 0 (mirror) to 1.
+	pub roughness: f32,
+	/// Roughness in green and metalness in blue.
+	pub metallic_roughness_texture: Option<&'a Texture>,
+	/// A tangent space normal map.  The model needs texture coordinates
+	/// that aren't stretched to nothing, so tangents can be found.
+	pub normal_texture: Option<&'a Texture>,
+	/// How much the normal map bends the normals.
+	pub normal_scale: f32,
+	/// Ambient occlusion in red, darkening ambient light.
+	pub occlusion_texture: Option<&'a Texture>,
+	/// How much the occlusion texture darkens, 0 to 1.
+	pub occlusion_strength: f32,
+	/// Light given off by the surface (linear RGB).
+	pub emissive: [f32; 3],
+	/// The emissive texture.
+	pub emissive_texture: Option<&'a Texture>,
+}
+
+impl<'a> Material<'a> {
+	/// Create a material with glTF's defaults: fully metallic, fully
+	/// rough, no textures and no emission.
+	pub fn new(base_color: [f32; 4]) -> Material<'a> {
+		Material {
+			base_color,
+			base_color_texture: None,
+			metallic: 1.0,
+			roughness: 1.0,
+			metallic_roughness_texture: None,
+			normal_texture: None,
+			normal_scale: 1.0,
+			occlusion_texture: None,
+			occlusion_strength: 1.0,
+			emissive: [0.0, 0.0, 0.0],
+			emissive_texture: None,
+		}
+	}
+
+	// Get the textures besides the base color: metallic-roughness,
+	// normal, occlusion and emissive.
+	pub(crate) fn maps(&self) -> [Option<&'a Texture>; 4] {
+		[
+			self.metallic_roughness_texture,
+			self.normal_texture,
+			self.occlusion_texture,
+			self.emissive_texture,
+		]
+	}
+
+	// Get the factors.
+	pub(crate) fn factors(&self) -> Factors {
+		Factors {
+			metallic: self.metallic,
+			roughness: self.roughness,
+			normal_scale: self.normal_scale,
+			occlusion_strength: self.occlusion_strength,
+			emissive: self.emissive,
+		}
+	}
+}
+
+/// A material's factors, besides the base color.
+#[derive(Copy, Clone)]
+pub struct Factors {
+	pub metallic: f32,
+	pub roughness: f32,
+	pub normal_scale: f32,
+	pub occlusion_strength: f32,
+	pub emissive: [f32; 3],
+}
+
+/// Find a tangent for each vertex (4 floats each, with the handedness of the
+/// bitangent in w), from the triangles' positions and texture coordinates.
+pub fn tangents(positions: &[f32], texcoords: &[f32], normals: &[f32],
+	topology: Topology, fans: &[(u32, u32)]) -> Vec<f32>
+{
+	let count = positions.len() / 4;
+	let mut tangents = vec![[0.0f32; 3]; count];
+	let mut bitangents = vec![[0.0f32; 3]; count];
+	let get = |data: &[f32], i: usize| {
+		[data[i * 4], data[i * 4 + 1], data[i * 4 + 2]]
+	};
+
+	for v in raster::triangles(topology, fans, count) {
+		let p = [get(positions, v[0]), get(positions, v[1]),
+			get(positions, v[2])];
+		let t = [get(texcoords, v[0]), get(texcoords, v[1]),
+			get(texcoords, v[2])];
+		let e1 = sub(p[1], p[0]);
+		let e2 = sub(p[2], p[0]);
+		let (s1, t1) = (t[1][0] - t[0][0], t[1][1] - t[0][1]);
+		let (s2, t2) = (t[2][0] - t[0][0], t[2][1] - t[0][1]);
+		let det = s1 * t2 - s2 * t1;
+
+		if det.abs() <= ::std::f32::EPSILON {
+			continue;
+		}
+
+		let r = 1.0 / det;
+		for j in v.iter() {
+			for k in 0..3 {
+				tangents[*j][k] += (e1[k] * t2 - e2[k] * t1) * r;
+				bitangents[*j][k] += (e2[k] * s1 - e1[k] * s2) * r;
+			}
+		}
+	}
+
+	let mut out = Vec::with_capacity(count * 4);
+	for i in 0..count {
+		let n = get(normals, i);
+		let t = tangents[i];
+
+		// Make the tangent perpendicular to the normal.
+		let t = normalize(sub(t, scale(n, dot(n, t))));
+		let w = if dot(cross(n, t), bitangents[i]) < 0.0 {
+			-1.0
+		} else {
+			1.0
+		};
+
+		out.extend_from_slice(&[t[0], t[1], t[2], w]);
+	}
+
+	out
+}
@@ -0,0 +1,41 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! 3D vector math, for the cameras, lights, materials and shadows.
+
+pub fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+	[a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+
+pub fn length(a: [f32; 3]) -> f32 {
+	dot(a, a).sqrt()
+}
+
+// Zero length vectors are returned as they are.
+pub fn normalize(a: [f32; 3]) -> [f32; 3] {
+	let length = length(a);
+
+	if length == 0.0 {
+		return a;
+	}
+
+	[a[0] / length, a[1] / length, a[2] / length]
+}
@@ -0,0 +1,188 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Post-processing for `set_post_process()`, run over the frame after the
+//! opaque and alpha shapes are drawn, and before the GUI is.
+
+/// A fullscreen post-processing pass.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostProcess {
+	/// Smooth jagged edges, with a simplified FXAA.
+	Fxaa,
+	/// Make colors brighter than `threshold` glow, adding `intensity`
+	/// times the blurred glow.
+	Bloom {
+		threshold: f32,
+		intensity: f32,
+	},
+	/// Scale colors by `exposure`, bring them back into 0 to 1 with
+	/// Reinhard tone-mapping, then gamma correct.
+	ToneMap {
+		exposure: f32,
+		gamma: f32,
+	},
+	/// Darken the corners, by `strength` (0 to 1).
+	Vignette {
+		strength: f32,
+	},
+	/// Look up each color in a `size`x`size`x`size` lookup table, stored
+	/// as `size` slices side by side (`size * size` pixels wide and `size`
+	/// tall, with blue choosing the slice).
+	ColorGrade {
+		lut: Vec<u32>,
+		size: u16,
+	},
+}
+
+// How far the bloom blur reaches, in pixels.
+const BLOOM_RADIUS: usize = 4;
+// How much brighter than its neighbors a pixel must be to count as an edge.
+const FXAA_THRESHOLD: f32 = 0.125;
+
+impl PostProcess {
+	// Run the pass over a frame's colors, rows top to bottom.
+	pub(crate) fn apply(&self, wh: (u16, u16), color: &mut [[f32; 4]]) {
+		let (w, h) = (wh.0 as usize, wh.1 as usize);
+
+		match *self {
+			PostProcess::Fxaa => fxaa(w, h, color),
+			PostProcess::Bloom { threshold, intensity } => {
+				let mut glow: Vec<[f32; 4]> = color.iter()
+					.map(|c| map(*c, |c| (c - threshold).max(0.0)))
+					.collect();
+
+				blur(w, h, &mut glow, true);
+				blur(w, h, &mut glow, false);
+
+				for (c, g) in color.iter_mut().zip(glow.iter()) {
+					for i in 0..3 {
+						c[i] += g[i] * intensity;
+					}
+				}
+			}
+			PostProcess::ToneMap { exposure, gamma } => {
+				for c in color.iter_mut() {
+					*c = map(*c, |c| {
+						let c = c * exposure;
+
+						(c / (1.0 + c)).powf(1.0 / gamma)
+					});
+				}
+			}
+			PostProcess::Vignette { strength } => {
+				for y in 0..h {
+					for x in 0..w {
+						// 0 at the center, 1 in the corners.
+						let dx = (x as f32 + 0.5) / w as f32
+							* 2.0 - 1.0;
+						let dy = (y as f32 + 0.5) / h as f32
+							* 2.0 - 1.0;
+						let d = (dx * dx + dy * dy) / 2.0;
+						let scale = 1.0 - strength * d;
+
+						let c = &mut color[y * w + x];
+						*c = map(*c, |c| c * scale);
+					}
+				}
+			}
+			PostProcess::ColorGrade { ref lut, size } => {
+				let size = size as usize;
+
+				if size == 0 || lut.len() < size * size * size {
+					return;
+				}
+
+				for c in color.iter_mut() {
+					let i = |c: f32| ((c.clamp(0.0, 1.0)
+						* (size - 1) as f32).round()) as usize;
+					let (r, g, b) = (i(c[0]), i(c[1]), i(c[2]));
+					let graded = lut[g * size * size + b * size + r];
+
+					for (j, c) in c.iter_mut().take(3).enumerate() {
+						*c = ((graded >> (j * 8)) & 0xFF) as f32
+							/ 255.0;
+					}
+				}
+			}
+		}
+	}
+}
+
+fn map<F: Fn(f32) -> f32>(c: [f32; 4], f: F) -> [f32; 4] {
+	[f(c[0]), f(c[1]), f(c[2]), c[3]]
+}
+
+fn luma(c: [f32; 4]) -> f32 {
+	c[0] * 0.299 + c[1] * 0.587 + c[2] * 0.114
+}
+
+// Box blur, either across or down.
+fn blur(w: usize, h: usize, color: &mut [[f32; 4]], across: bool) {
+	let src = color.to_vec();
+	let r = BLOOM_RADIUS as isize;
+
+	for y in 0..h {
+		for x in 0..w {
+			let mut sum = [0.0; 4];
+			let mut count = 0.0;
+
+			for d in -r..r + 1 {
+				let (nx, ny) = if across {
+					(x as isize + d, y as isize)
+				} else {
+					(x as isize, y as isize + d)
+				};
+
+				if nx < 0 || ny < 0 || nx >= w as isize
+					|| ny >= h as isize
+				{
+					continue;
+				}
+
+				let c = src[ny as usize * w + nx as usize];
+				for i in 0..4 {
+					sum[i] += c[i];
+				}
+				count += 1.0;
+			}
+
+			color[y * w + x] = map(sum, |c| c / count);
+		}
+	}
+}
+
+// Blend pixels on high contrast edges with their neighbors.
+fn fxaa(w: usize, h: usize, color: &mut [[f32; 4]]) {
+	let src = color.to_vec();
+
+	for y in 1..h.saturating_sub(1) {
+		for x in 1..w.saturating_sub(1) {
+			let center = src[y * w + x];
+			let neighbors = [
+				src[(y - 1) * w + x],
+				src[(y + 1) * w + x],
+				src[y * w + x - 1],
+				src[y * w + x + 1],
+			];
+			let lumas: Vec<f32> = neighbors.iter()
+				.map(|c| luma(*c))
+				.collect();
+			let min = lumas.iter().fold(luma(center), |a, b| a.min(*b));
+			let max = lumas.iter().fold(luma(center), |a, b| a.max(*b));
+
+			if max - min < FXAA_THRESHOLD {
+				continue;
+			}
+
+			let c = &mut color[y * w + x];
+			for i in 0..3 {
+				let average = neighbors.iter().map(|n| n[i])
+					.sum::<f32>() / 4.0;
+
+				c[i] = (center[i] + average) / 2.0;
+			}
+		}
+	}
+}
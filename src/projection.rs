@@ -0,0 +1,108 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Projections for `Display::set_projection()`.
+
+use adi_gpu_base::*;
+
+use Error;
+
+/// How the scene is projected onto the screen.  The camera looks down +Z.
+/// With `reversed_z`, the near plane has a depth of 1 and the far plane 0,
+/// which spreads depth precision more evenly for far away shapes (but only
+/// on a `Headless` display; see `Display::set_projection()`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+	/// Things farther away look smaller.  `fov` is the vertical field of
+	/// view in radians.
+	Perspective {
+		fov: f32,
+		near: f32,
+		far: f32,
+		reversed_z: bool,
+	},
+	/// Things stay the same size no matter how far away.  `height` is how
+	/// many units tall the view is.
+	Orthographic {
+		height: f32,
+		near: f32,
+		far: f32,
+		reversed_z: bool,
+	},
+}
+
+impl Projection {
+	/// Whether the near plane has a depth of 1 instead of 0.
+	pub fn reversed_z(&self) -> bool {
+		match *self {
+			Projection::Perspective { reversed_z, .. } => reversed_z,
+			Projection::Orthographic { reversed_z, .. } => reversed_z,
+		}
+	}
+
+	// Check that the planes, field of view and height are in range.  NaN
+	// fails every check.
+	pub(crate) fn check(&self) -> Result<(), Error> {
+		let valid = match *self {
+			Projection::Perspective { fov, near, far, .. } => {
+				near > 0.0 && far > near && fov > 0.0 && fov < PI
+			}
+			Projection::Orthographic { height, near, far, .. } => {
+				near > 0.0 && far > near && height > 0.0
+			}
+		};
+
+		if !valid {
+			return Err(Error::InvalidProjection);
+		}
+		Ok(())
+	}
+
+	// Get the projection matrix for an aspect ratio (width / height).
+	pub(crate) fn transform(&self, ar: f32) -> Transform {
+		// Matrices are written column by column.
+		match *self {
+			Projection::Perspective { fov, near, far, reversed_z } => {
+				let y = 1.0 / (fov * 0.5).tan();
+				let x = y / ar;
+				// Map near to -1 and far to 1, after dividing by z.
+				let z = (far + near) / (far - near);
+				let w = -2.0 * far * near / (far - near);
+				let (z, w) = if reversed_z { (-z, -w) } else { (z, w) };
+
+				Transform(mat4!(
+					x, 0.0, 0.0, 0.0,
+					0.0, y, 0.0, 0.0,
+					0.0, 0.0, z, 1.0,
+					0.0, 0.0, w, 0.0
+				))
+			}
+			Projection::Orthographic { height, near, far, reversed_z } => {
+				let y = 2.0 / height;
+				let x = y / ar;
+				// Map near to -1 and far to 1.
+				let z = 2.0 / (far - near);
+				let w = -(far + near) / (far - near);
+				let (z, w) = if reversed_z { (-z, -w) } else { (z, w) };
+
+				Transform(mat4!(
+					x, 0.0, 0.0, 0.0,
+					0.0, y, 0.0, 0.0,
+					0.0, 0.0, z, 0.0,
+					0.0, 0.0, w, 1.0
+				))
+			}
+		}
+	}
+}
+
+// Get the projection matrix for an aspect ratio, or the default projection if
+// there's no `lens`.
+pub(crate) fn transform(lens: Option<Projection>, ar: f32) -> Transform {
+	match lens {
+		Some(lens) => lens.transform(ar),
+		None => ::base::projection(ar, 0.5 * PI),
+	}
+}
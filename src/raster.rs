@@ -0,0 +1,637 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! CPU reference rasterizer, used when there's no OpenGL context to draw with.
+//! It follows the GLSL in `src/shaders/` and OpenGL's clipping, culling, depth
+//! and blending rules, so its output can be diffed against golden images.
+
+use adi_gpu_base::*;
+use asi_opengl::Topology;
+
+use fog;
+use light::{self, Light};
+use material::Factors;
+use math;
+use shadow::ShadowMap;
+use {
+	STYLE_GRADIENT, STYLE_TEXTURE, STYLE_FADED, STYLE_TINTED, STYLE_SOLID,
+	STYLE_COMPLEX, STYLE_VG, STYLE_LIT_SOLID, STYLE_LIT_TEXTURE,
+	STYLE_LIT_TINTED, STYLE_PBR,
+};
+
+// How many floats are interpolated across a triangle.
+const VARYINGS: usize = 24;
+
+/// A texture stored in memory.
+pub struct Image {
+	pub w: u16,
+	pub h: u16,
+	pub pixels: Vec<u32>,
+}
+
+impl Image {
+	// Nearest-neighbor lookup with repeat wrapping, like `texture2D()`.
+	fn sample(&self, s: f32, t: f32) -> [f32; 4] {
+		if self.w == 0 || self.h == 0 {
+			return [0.0, 0.0, 0.0, 0.0];
+		}
+
+		let w = self.w as usize;
+		let h = self.h as usize;
+		let x = (((s - s.floor()) * w as f32) as usize).min(w - 1);
+		let y = (((t - t.floor()) * h as f32) as usize).min(h - 1);
+
+		unpack(self.pixels[y * w + x])
+	}
+}
+
+/// Color and depth buffers that shapes get rasterized into.
+pub struct Frame {
+	wh: (u16, u16),
+	color: Vec<[f32; 4]>,
+	depth: Vec<f32>,
+}
+
+impl Frame {
+	/// Create a new frame, `wh` pixels in size.
+	pub fn new(wh: (u16, u16)) -> Frame {
+		let size = wh.0 as usize * wh.1 as usize;
+
+		Frame {
+			wh,
+			color: vec![[0.0, 0.0, 0.0, 1.0]; size],
+			depth: vec![1.0; size],
+		}
+	}
+
+	/// Get the width and height of the frame.
+	pub fn wh(&self) -> (u16, u16) {
+		self.wh
+	}
+
+	/// Clear the color buffer to `rgb`, and reset the depth buffer to the
+	/// far plane (0 if `reversed_z`, otherwise 1).
+	pub fn clear(&mut self, rgb: (f32, f32, f32), reversed_z: bool) {
+		let far = if reversed_z { 0.0 } else { 1.0 };
+
+		for pixel in self.color.iter_mut() {
+			*pixel = [rgb.0, rgb.1, rgb.2, 1.0];
+		}
+		for depth in self.depth.iter_mut() {
+			*depth = far;
+		}
+	}
+
+	/// Get the colors for modifying, rows top to bottom.
+	pub fn color_mut(&mut self) -> &mut [[f32; 4]] {
+		&mut self.color
+	}
+
+	/// Get the depth buffer, rows top to bottom.
+	pub fn depth(&self) -> &[f32] {
+		&self.depth
+	}
+
+	/// Get the RGBA pixels, rows top to bottom.
+	pub fn pixels(&self) -> Vec<u32> {
+		self.color.iter().map(|c| pack(*c)).collect()
+	}
+}
+
+/// Count the pixels that differ between two RGBA images by more than
+/// `tolerance` in any channel.  Images of different sizes differ everywhere.
+pub fn diff(a: &[u32], b: &[u32], tolerance: u8) -> usize {
+	if a.len() != b.len() {
+		return a.len().max(b.len());
+	}
+
+	a.iter().zip(b.iter()).filter(|&(a, b)| {
+		(0..4).any(|i| {
+			let a = ((a >> (i * 8)) & 0xFF) as i16;
+			let b = ((b >> (i * 8)) & 0xFF) as i16;
+
+			(a - b).abs() > tolerance as i16
+		})
+	}).count()
+}
+
+/// The uniforms for one draw, named after the GLSL uniforms they replace.
+pub struct Uniforms<'a> {
+	pub models_tfm: Transform,
+	pub matrix: Option<Transform>,
+	/// Applied after `matrix`, once the fog distance is found.
+	pub squeeze: Transform,
+	pub fog: Option<([f32; 4], [f32; 4])>,
+	/// Colors around the horizon that the fog takes its color from.
+	pub sky: Option<&'a fog::Sky>,
+	pub alpha: f32,
+	pub color: [f32; 4],
+	pub texture: Option<&'a Image>,
+	pub depth_test: bool,
+	/// Near is at depth 1, and the depth test is GREATER instead of LESS.
+	pub reversed_z: bool,
+	/// Only draw inside of this rectangle: left, top, width and height.
+	pub viewport: Option<(u16, u16, u16, u16)>,
+	pub lights: &'a [Light],
+	/// Where the camera is in the world, for the lit styles.
+	pub eye: [f32; 3],
+	/// Shadow maps for each of `lights`, or empty for no shadows.
+	pub shadows: &'a [Option<ShadowMap>],
+	/// The PBR style's factors, and its metallic-roughness, normal,
+	/// occlusion and emissive textures.
+	pub material: Option<(Factors, [Option<&'a Image>; 4])>,
+}
+
+/// The vertex attributes for one draw, 4 floats per vertex.
+pub struct Attributes<'a> {
+	pub position: &'a [f32],
+	pub texpos: Option<&'a [f32]>,
+	pub acolor: Option<&'a [f32]>,
+	pub normal: Option<&'a [f32]>,
+	pub tangent: Option<&'a [f32]>,
+}
+
+// A transformed vertex: clip space position, then texcoord, vcolor, z, world
+// position, world normal, world tangent (with handedness), height, clip space
+// z and w before the squeeze, and world x and z from the camera.
+#[derive(Copy, Clone)]
+struct Vertex {
+	position: [f32; 4],
+	varying: [f32; VARYINGS],
+}
+
+/// Draw triangle fans (or, for `Topology::Triangles`, triangle lists) with
+/// one of the shape styles.  Each vertex is only transformed once.
+pub fn draw(frame: &mut Frame, style: usize, uniforms: &Uniforms,
+	attributes: &Attributes, topology: Topology, fans: &[(u32, u32)])
+{
+	let count = attributes.position.len() / 4;
+	let mut vertices = vec![None; count];
+
+	for t in triangles(topology, fans, count) {
+		let mut triangle_vertices = [None; 3];
+
+		for (j, &i) in t.iter().enumerate() {
+			let v = *vertices[i].get_or_insert_with(|| {
+				vertex(style, uniforms, attributes, i)
+			});
+
+			triangle_vertices[j] = Some(v);
+		}
+
+		triangle(frame, style, uniforms, [triangle_vertices[0].unwrap(),
+			triangle_vertices[1].unwrap(),
+			triangle_vertices[2].unwrap()]);
+	}
+}
+
+/// Split triangle fans (or, for `Topology::Triangles`, triangle lists) into
+/// the vertex indices of each triangle.  Fans that run past the `count`
+/// vertices are cut short, so a bad `model()` can't panic.
+pub fn triangles(topology: Topology, fans: &[(u32, u32)], count: usize)
+	-> Vec<[usize; 3]>
+{
+	let mut triangles = vec![];
+
+	for fan in fans.iter() {
+		let (start, end) = (fan.0 as usize, (fan.1 as usize).min(count));
+
+		match topology {
+			Topology::Triangles => {
+				for i in 0..end.saturating_sub(start) / 3 {
+					let a = start + i * 3;
+
+					triangles.push([a, a + 1, a + 2]);
+				}
+			}
+			_ => {
+				for i in start + 1..end.saturating_sub(1) {
+					triangles.push([start, i, i + 1]);
+				}
+			}
+		}
+	}
+
+	triangles
+}
+
+// Emulate the vertex shaders.
+fn vertex(style: usize, uniforms: &Uniforms, attributes: &Attributes,
+	i: usize) -> Vertex
+{
+	let p = &attributes.position[i * 4..i * 4 + 4];
+	let mut place = uniforms.models_tfm.0 * vec4!(p[0], p[1], p[2], 1.0);
+	let world = place;
+	let normal = match attributes.normal {
+		Some(n) => transform_normal(uniforms.models_tfm,
+			[n[i * 4], n[i * 4 + 1], n[i * 4 + 2]]),
+		None => [0.0, 0.0, 0.0],
+	};
+	let tangent = match attributes.tangent {
+		Some(t) => {
+			let w = t[i * 4 + 3];
+			let t = uniforms.models_tfm.0 * vec4!(t[i * 4],
+				t[i * 4 + 1], t[i * 4 + 2], 0.0);
+
+			[t.x, t.y, t.z, w]
+		}
+		None => [0.0, 0.0, 0.0, 1.0],
+	};
+
+	if let Some(ref matrix) = uniforms.matrix {
+		place = matrix.0 * place;
+	}
+	// Before the viewport squeezes it.
+	let z = math::length([place.x, place.y, place.z]);
+	let mut clip = [0.0, 1.0];
+	if uniforms.matrix.is_some() {
+		clip = [place.z, place.w];
+		place = uniforms.squeeze.0 * place;
+	}
+
+	let texcoord = match attributes.texpos {
+		Some(t) => {
+			let t = &t[i * 4..i * 4 + 4];
+			if style == STYLE_FADED {
+				[t[0], t[1], t[2], t[3] * uniforms.alpha]
+			} else {
+				[t[0], t[1], t[2], t[3]]
+			}
+		}
+		None => [0.0, 0.0, 0.0, 1.0],
+	};
+	let vcolor = match attributes.acolor {
+		Some(c) => [c[i * 4], c[i * 4 + 1], c[i * 4 + 2], c[i * 4 + 3]],
+		None => [1.0, 1.0, 1.0, 1.0],
+	};
+
+	Vertex {
+		position: [place.x, -place.y, place.z, place.w],
+		varying: [
+			texcoord[0], texcoord[1], texcoord[2], texcoord[3],
+			vcolor[0], vcolor[1], vcolor[2], vcolor[3],
+			z,
+			world.x, world.y, world.z,
+			normal[0], normal[1], normal[2],
+			tangent[0], tangent[1], tangent[2], tangent[3],
+			-world.y, // +Y is down.
+			clip[0], clip[1],
+			world.x - uniforms.eye[0], world.z - uniforms.eye[2],
+		],
+	}
+}
+
+// Emulate the fragment shaders.  `None` if the fragment is discarded.
+fn fragment(style: usize, uniforms: &Uniforms, varying: &[f32; VARYINGS])
+	-> Option<[f32; 4]>
+{
+	let texcoord = [varying[0], varying[1], varying[2], varying[3]];
+	let vcolor = [varying[4], varying[5], varying[6], varying[7]];
+	let lit = |c: [f32; 4]| {
+		let world = [varying[9], varying[10], varying[11]];
+		let normal = [varying[12], varying[13], varying[14]];
+		let rgb = light::shade(uniforms.lights,
+			&visibility(uniforms, world), uniforms.eye, world,
+			normal, [c[0], c[1], c[2]]);
+
+		[rgb[0], rgb[1], rgb[2], c[3]]
+	};
+	let sampled = || match uniforms.texture {
+		Some(texture) => {
+			let s = texture.sample(texcoord[0], texcoord[1]);
+			[s[0], s[1], s[2], s[3] * texcoord[3]]
+		}
+		None => [0.0, 0.0, 0.0, 0.0],
+	};
+
+	let out_color = match style {
+		STYLE_SOLID => uniforms.color,
+		STYLE_GRADIENT => vcolor,
+		STYLE_TEXTURE | STYLE_FADED => sampled(),
+		STYLE_TINTED => mul(sampled(), uniforms.color),
+		STYLE_COMPLEX => mul(sampled(), vcolor),
+		STYLE_VG => {
+			// Keep only the filled side of the curve u² - v = 0.
+			let curve = texcoord[0] * texcoord[0] - texcoord[1];
+			if curve * texcoord[2] > 0.0 {
+				return None;
+			}
+			uniforms.color
+		}
+		STYLE_LIT_SOLID => lit(uniforms.color),
+		STYLE_LIT_TEXTURE => lit(sampled()),
+		STYLE_LIT_TINTED => lit(mul(sampled(), uniforms.color)),
+		STYLE_PBR => {
+			let base = match uniforms.texture {
+				Some(_) => mul(sampled(), uniforms.color),
+				None => uniforms.color,
+			};
+
+			pbr(uniforms, varying, base)
+		}
+		_ => unreachable!(),
+	};
+
+	Some(if let Some((fog, range)) = uniforms.fog {
+		let amount = fog::amount(fog, range, varying[8], varying[19]);
+		let color = fog::color(fog, uniforms.sky,
+			[varying[22], varying[23]]);
+
+		mix(out_color, [color[0], color[1], color[2], 1.0], amount)
+	} else {
+		out_color
+	})
+}
+
+// Emulate `pbr-frag.glsl`, plus the textures that it can't sample.
+fn pbr(uniforms: &Uniforms, varying: &[f32; VARYINGS], base: [f32; 4])
+	-> [f32; 4]
+{
+	let (factors, maps) = match uniforms.material {
+		Some(material) => material,
+		None => return base,
+	};
+	let sample = |i: usize| maps[i].map(|map| {
+		map.sample(varying[0], varying[1])
+	});
+	let world = [varying[9], varying[10], varying[11]];
+	let mut normal = [varying[12], varying[13], varying[14]];
+	let mut metallic = factors.metallic;
+	let mut roughness = factors.roughness;
+	let mut occlusion = 1.0;
+	let mut emissive = factors.emissive;
+
+	if let Some(map) = sample(0) {
+		roughness *= map[1];
+		metallic *= map[2];
+	}
+	if let Some(map) = sample(1) {
+		// Bend the normal with the tangent space normal map.
+		let (n, t) = (normal, [varying[15], varying[16], varying[17]]);
+		let b = [
+			(n[1] * t[2] - n[2] * t[1]) * varying[18],
+			(n[2] * t[0] - n[0] * t[2]) * varying[18],
+			(n[0] * t[1] - n[1] * t[0]) * varying[18],
+		];
+		let x = (map[0] * 2.0 - 1.0) * factors.normal_scale;
+		let y = (map[1] * 2.0 - 1.0) * factors.normal_scale;
+		let z = map[2] * 2.0 - 1.0;
+
+		for i in 0..3 {
+			normal[i] = t[i] * x + b[i] * y + n[i] * z;
+		}
+	}
+	if let Some(map) = sample(2) {
+		occlusion = 1.0 + factors.occlusion_strength * (map[0] - 1.0);
+	}
+	if let Some(map) = sample(3) {
+		for i in 0..3 {
+			emissive[i] *= map[i];
+		}
+	}
+
+	let rgb = light::shade_pbr(uniforms.lights,
+		&visibility(uniforms, world), uniforms.eye, world, normal,
+		[base[0], base[1], base[2]], metallic, roughness, occlusion);
+
+	[rgb[0] + emissive[0], rgb[1] + emissive[1], rgb[2] + emissive[2],
+		base[3]]
+}
+
+// Transform a normal by the inverse-transpose of `transform` (its cofactors,
+// flipped if it mirrors), like `transform_normal()` in the lit vertex shaders.
+fn transform_normal(transform: Transform, n: [f32; 3]) -> [f32; 3] {
+	let column = |x, y, z| {
+		let c = transform.0 * vec4!(x, y, z, 0.0);
+
+		[c.x, c.y, c.z]
+	};
+	let (a, b, c) = (column(1.0, 0.0, 0.0), column(0.0, 1.0, 0.0),
+		column(0.0, 0.0, 1.0));
+	let (bc, ca, ab) = (math::cross(b, c), math::cross(c, a),
+		math::cross(a, b));
+	let sign = if math::dot(a, bc) < 0.0 { -1.0 } else { 1.0 };
+	let row = |i: usize| sign * (bc[i] * n[0] + ca[i] * n[1] + ab[i] * n[2]);
+
+	[row(0), row(1), row(2)]
+}
+
+// Look up how much of each light reaches `world`, through the shadow maps.
+fn visibility(uniforms: &Uniforms, world: [f32; 3]) -> Vec<f32> {
+	uniforms.shadows.iter()
+		.map(|s| s.as_ref().map(|s| s.visibility(world)).unwrap_or(1.0))
+		.collect()
+}
+
+// Clip a triangle against the near plane (z >= -w, or z <= w when reversed),
+// like OpenGL does before the perspective divide, then rasterize what's left.
+fn triangle(frame: &mut Frame, style: usize, uniforms: &Uniforms,
+	v: [Vertex; 3])
+{
+	let distance = |v: &Vertex| if uniforms.reversed_z {
+		v.position[3] - v.position[2]
+	} else {
+		v.position[2] + v.position[3]
+	};
+	let mut polygon = Vec::with_capacity(4);
+
+	for i in 0..3 {
+		let (a, b) = (v[i], v[(i + 1) % 3]);
+		let (da, db) = (distance(&a), distance(&b));
+
+		if da >= 0.0 {
+			polygon.push(a);
+		}
+		if (da >= 0.0) != (db >= 0.0) {
+			polygon.push(lerp(a, b, da / (da - db)));
+		}
+	}
+
+	for i in 1..polygon.len().saturating_sub(1) {
+		rasterize(frame, style, uniforms,
+			[polygon[0], polygon[i], polygon[i + 1]]);
+	}
+}
+
+// Interpolate between two vertices in clip space.
+fn lerp(a: Vertex, b: Vertex, t: f32) -> Vertex {
+	let mut out = a;
+
+	for i in 0..4 {
+		out.position[i] += (b.position[i] - a.position[i]) * t;
+	}
+	for i in 0..VARYINGS {
+		out.varying[i] += (b.varying[i] - a.varying[i]) * t;
+	}
+
+	out
+}
+
+// Rasterize one clipped triangle, with depth testing and alpha blending.
+fn rasterize(frame: &mut Frame, style: usize, uniforms: &Uniforms,
+	v: [Vertex; 3])
+{
+	if v.iter().any(|v| v.position[3] <= ::std::f32::EPSILON) {
+		return;
+	}
+
+	let (w, h) = (frame.wh.0 as f32, frame.wh.1 as f32);
+	let mut screen = [[0.0f32; 3]; 3];
+	for i in 0..3 {
+		let p = v[i].position;
+		screen[i] = [
+			(p[0] / p[3] + 1.0) * 0.5 * w,
+			(p[1] / p[3] + 1.0) * 0.5 * h,
+			p[2] / p[3],
+		];
+	}
+
+	let area = edge(screen[0], screen[1], screen[2]);
+	// Back faces are culled, front faces wind counter-clockwise.
+	if area <= 0.0 {
+		return;
+	}
+
+	// Pixels exactly on an edge belong to the triangle only for top and
+	// left edges, so triangles sharing an edge don't blend it twice.
+	let bias = [
+		top_left(screen[1], screen[2]),
+		top_left(screen[2], screen[0]),
+		top_left(screen[0], screen[1]),
+	];
+
+	let min_x = screen.iter().fold(w, |a, s| a.min(s[0])).max(0.0);
+	let max_x = screen.iter().fold(0.0, |a: f32, s| a.max(s[0])).min(w);
+	let min_y = screen.iter().fold(h, |a, s| a.min(s[1])).max(0.0);
+	let max_y = screen.iter().fold(0.0, |a: f32, s| a.max(s[1])).min(h);
+
+	for y in min_y as usize..max_y.ceil() as usize {
+		for x in min_x as usize..max_x.ceil() as usize {
+			if !inside_viewport(uniforms, frame.wh, x, y) {
+				continue;
+			}
+
+			let p = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+			let e = [
+				edge(screen[1], screen[2], p),
+				edge(screen[2], screen[0], p),
+				edge(screen[0], screen[1], p),
+			];
+
+			if (0..3).any(|i| e[i] < 0.0 || (e[i] == 0.0 && !bias[i]))
+			{
+				continue;
+			}
+
+			let b = [e[0] / area, e[1] / area, e[2] / area];
+			let depth = b[0] * screen[0][2] + b[1] * screen[1][2]
+				+ b[2] * screen[2][2];
+			if !(-1.0..=1.0).contains(&depth) {
+				continue;
+			}
+
+			// Rows are stored top to bottom.
+			let index = (frame.wh.1 as usize - 1 - y)
+				* frame.wh.0 as usize + x;
+			let depth = depth * 0.5 + 0.5;
+			let hidden = if uniforms.reversed_z {
+				depth <= frame.depth[index]
+			} else {
+				depth >= frame.depth[index]
+			};
+			if uniforms.depth_test && hidden {
+				continue;
+			}
+
+			// Perspective-correct interpolation of the varyings.
+			let pw = [
+				b[0] / v[0].position[3],
+				b[1] / v[1].position[3],
+				b[2] / v[2].position[3],
+			];
+			let sum = pw[0] + pw[1] + pw[2];
+			let mut varying = [0.0; VARYINGS];
+			for (i, out) in varying.iter_mut().enumerate() {
+				*out = (pw[0] * v[0].varying[i]
+					+ pw[1] * v[1].varying[i]
+					+ pw[2] * v[2].varying[i]) / sum;
+			}
+
+			// Like the fragment shaders, discard what's beyond the
+			// viewport's near and far planes, which the squeeze moves
+			// into other viewports' depth.
+			if varying[20].abs() > varying[21] {
+				continue;
+			}
+
+			let src = match fragment(style, uniforms, &varying) {
+				Some(src) => src,
+				None => continue,
+			};
+			let dst = frame.color[index];
+			frame.color[index] = mix(dst, src, src[3]);
+
+			if uniforms.depth_test {
+				frame.depth[index] = depth;
+			}
+		}
+	}
+}
+
+// Emulate the fragment shaders discarding pixels outside of the viewport.
+fn inside_viewport(uniforms: &Uniforms, wh: (u16, u16), x: usize, y: usize)
+	-> bool
+{
+	let (left, top, w, h) = match uniforms.viewport {
+		Some(viewport) => viewport,
+		None => return true,
+	};
+	// Rows are counted from the top.
+	let row = wh.1 as usize - 1 - y;
+
+	x >= left as usize && x < (left + w) as usize
+		&& row >= top as usize && row < (top + h) as usize
+}
+
+fn edge(a: [f32; 3], b: [f32; 3], p: [f32; 3]) -> f32 {
+	(b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+// Whether the edge from `a` to `b` is a top or left edge of a counter-clockwise
+// triangle (with y going up).
+fn top_left(a: [f32; 3], b: [f32; 3]) -> bool {
+	(a[1] == b[1] && b[0] < a[0]) || b[1] < a[1]
+}
+
+fn mul(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+	[a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+	[
+		a[0] + (b[0] - a[0]) * t,
+		a[1] + (b[1] - a[1]) * t,
+		a[2] + (b[2] - a[2]) * t,
+		a[3] + (b[3] - a[3]) * t,
+	]
+}
+
+// Unpack an RGBA pixel (red in the lowest byte).
+fn unpack(pixel: u32) -> [f32; 4] {
+	[
+		(pixel & 0xFF) as f32 / 255.0,
+		((pixel >> 8) & 0xFF) as f32 / 255.0,
+		((pixel >> 16) & 0xFF) as f32 / 255.0,
+		(pixel >> 24) as f32 / 255.0,
+	]
+}
+
+// Pack an RGBA pixel (red in the lowest byte).
+fn pack(color: [f32; 4]) -> u32 {
+	let c = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u32;
+
+	c(color[0]) | (c(color[1]) << 8) | (c(color[2]) << 16)
+		| (c(color[3]) << 24)
+}
@@ -0,0 +1,107 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! A list of models, textures, texture coordinates or gradients, with
+//! generational handles so that handles to dropped resources are caught.
+
+use handle;
+use Error;
+
+pub struct Resources<T> {
+	// Id of the display the resources are on.
+	display: usize,
+	// Generation of each slot, and the resource.  `None` if dropped.
+	slots: Vec<(usize, Option<T>)>,
+	// Indices of empty slots, to be reused.
+	free: Vec<usize>,
+}
+
+impl<T> Resources<T> {
+	pub fn new(display: usize) -> Resources<T> {
+		Resources { display, slots: vec![], free: vec![] }
+	}
+
+	/// Add a resource, returning its handle.
+	pub fn push(&mut self, resource: T) -> usize {
+		let index = if let Some(index) = self.free.pop() {
+			self.slots[index].1 = Some(resource);
+			index
+		} else {
+			self.slots.push((0, Some(resource)));
+			self.slots.len() - 1
+		};
+
+		handle::pack(index, self.display, self.slots[index].0)
+	}
+
+	/// Remove a resource, freeing its slot.  Handles to it become stale.
+	pub fn remove(&mut self, handle: usize) -> Result<T, Error> {
+		let index = self.index(handle)?;
+		let slot = &mut self.slots[index];
+		let resource = slot.1.take().unwrap();
+
+		slot.0 = handle::next_generation(slot.0, ::std::usize::MAX);
+		self.free.push(index);
+
+		Ok(resource)
+	}
+
+	/// Get a resource.
+	pub fn get(&self, handle: usize) -> Result<&T, Error> {
+		let index = self.index(handle)?;
+
+		Ok(self.slots[index].1.as_ref().unwrap())
+	}
+
+	/// Get a resource for modifying.
+	pub fn get_mut(&mut self, handle: usize) -> Result<&mut T, Error> {
+		let index = self.index(handle)?;
+
+		Ok(self.slots[index].1.as_mut().unwrap())
+	}
+
+	// Get the slot index of a handle, if it refers to a live resource.
+	fn index(&self, handle: usize) -> Result<usize, Error> {
+		let (index, generation) = handle::unpack(handle, self.display)?;
+		let slot = self.slots.get(index).ok_or(Error::InvalidHandle)?;
+
+		if slot.1.is_none() || slot.0 != generation {
+			return Err(Error::StaleHandle);
+		}
+
+		Ok(index)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn generations() {
+		let mut resources = Resources::new(1);
+		let a = resources.push('a');
+
+		assert_eq!(resources.remove(a), Ok('a'));
+		assert_eq!(resources.get(a), Err(Error::StaleHandle));
+
+		// The slot is reused, but the old handle stays stale.
+		let b = resources.push('b');
+		assert_ne!(a, b);
+		assert_eq!(resources.get(b), Ok(&'b'));
+		assert_eq!(resources.get(a), Err(Error::StaleHandle));
+		assert_eq!(resources.remove(a), Err(Error::StaleHandle));
+	}
+
+	#[test]
+	fn wrong_display() {
+		let mut resources = Resources::new(1);
+		let mut other = Resources::<char>::new(2);
+		let a = resources.push('a');
+
+		assert_eq!(other.get_mut(a), Err(Error::WrongDisplay));
+		assert_eq!(resources.get(a + 1), Err(Error::InvalidHandle));
+	}
+}
@@ -0,0 +1,61 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+// Fog, shared by the built-in fragment shaders, which it's inserted into after
+// their `precision` line.
+
+#define SKY_RING 16
+
+uniform vec4 fog; // The fog color, and the mode in alpha.
+uniform vec4 range; // Start, range or density, height fog base, falloff.
+uniform vec4 sky[SKY_RING]; // Around the horizon, alpha 0 for no sky.
+
+varying float z;
+varying float height;
+varying vec2 around; // World x and z from the camera.
+
+// How much fog there is, 0 to 1.  Modes are 0 cubic, 1 linear, 2 exponential
+// and 3 exponential squared.  Height fog thins out above its base.
+float fog_amount() {
+	float amount;
+
+	if(fog.a < 0.5) {
+		float linear = clamp((z-range.x) / range.y, 0.0, 1.0);
+		amount = linear * linear * linear;
+	} else if(fog.a < 1.5) {
+		amount = clamp((z-range.x) / range.y, 0.0, 1.0);
+	} else if(fog.a < 2.5) {
+		amount = 1.0 - exp(-range.y * z);
+	} else {
+		float d = range.y * z;
+		amount = 1.0 - exp(-d * d);
+	}
+
+	if(range.w > 0.0) {
+		amount *= exp(-max(height - range.z, 0.0) * range.w);
+	}
+
+	return amount;
+}
+
+// The fog color: blended from the two colors of the sky's horizon nearest the
+// direction to the fragment, starting behind, or the fog's own without a sky.
+vec3 fog_color() {
+	if(sky[0].a < 0.5) {
+		return fog.rgb;
+	}
+
+	float n = float(SKY_RING);
+	float s = (atan(around.x, around.y) / 6.2831853 + 0.5) * n - 0.5;
+	vec3 color = vec3(0.0);
+
+	for(int i = 0; i < SKY_RING; i++) {
+		float d = abs(s - float(i));
+
+		color += sky[i].rgb * max(1.0 - min(d, n - d), 0.0);
+	}
+
+	return color;
+}
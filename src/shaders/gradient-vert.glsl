@@ -12,18 +12,30 @@ attribute vec4 acolor;
 uniform mat4 models_tfm; // The Models' Transform Matrix
 uniform int has_camera; // 0 no, 1 yes, 2 fog
 uniform mat4 matrix; // The Camera's Transform & Projection Matrix
+uniform mat4 squeeze; // Into the viewport, after finding z
+uniform vec4 eye; // Where the camera is in the world.
 
 varying vec4 vcolor;
 varying float z;
+varying float height; // Height in the world, for height fog.
+varying vec2 clip; // Clip space z and w, before the squeeze.
+varying vec2 around; // World x and z from the camera, for the sky.
 
 void main() {
 	vec4 place = models_tfm * vec4(position.xyz, 1.0);
+	height = -place.y; // +Y is down.
+	around = place.xz - eye.xz;
 
 	if(has_camera == 1) {
 		place = matrix * place;
 	}
+	z = length(place.xyz); // Before the viewport squeezes it.
+	clip = vec2(0.0, 1.0);
+	if(has_camera == 1) {
+		clip = place.zw;
+		place = squeeze * place;
+	}
 
 	gl_Position = vec4(place.x, -place.y, place.z, place.w);
 	vcolor = acolor;
-	z = length(gl_Position.xyz);
 }
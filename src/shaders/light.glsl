@@ -0,0 +1,84 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+// Lights, shared by the lit fragment shaders, which it's inserted into after
+// the fog.
+
+#define MAX_LIGHTS 8
+#define SHININESS 32.0
+#define SPECULAR 0.5
+
+uniform int light_count;
+uniform vec4 light_position[MAX_LIGHTS]; // w is the kind of light.
+uniform vec4 light_direction[MAX_LIGHTS]; // w is cos of the spot angle.
+uniform vec4 light_color[MAX_LIGHTS]; // w is the range (0 for none).
+uniform vec4 eye; // Where the camera is in the world.
+
+varying vec3 world;
+varying vec3 world_normal;
+
+// Get the direction `l` towards a light that isn't ambient, and how much of
+// it reaches here.  Kinds are 0 ambient, 1 directional, 2 point, 3 spot.
+float incoming(vec4 position, vec4 direction, vec4 color, out vec3 l) {
+	float attenuation = 1.0;
+
+	if(position.w < 1.5) {
+		l = normalize(-direction.xyz);
+		return attenuation;
+	}
+
+	vec3 to = position.xyz - world;
+
+	if(color.w > 0.0) {
+		float fade = clamp(1.0 - length(to) / color.w, 0.0, 1.0);
+		attenuation = fade * fade;
+	}
+	if(position.w > 2.5) {
+		float cone = direction.w;
+		float theta = -dot(normalize(to), normalize(direction.xyz));
+
+		attenuation *= smoothstep(cone, cone + (1.0 - cone) * 0.1,
+			theta);
+	}
+	l = normalize(to);
+
+	return attenuation;
+}
+
+// Blinn-Phong lighting.
+vec3 shade(vec3 base) {
+	vec3 n = normalize(world_normal);
+	vec3 v = normalize(eye.xyz - world);
+	vec3 total = vec3(0.0);
+
+	for(int i = 0; i < MAX_LIGHTS; i++) {
+		if(i >= light_count) {
+			break;
+		}
+
+		vec3 color = light_color[i].rgb;
+
+		if(light_position[i].w < 0.5) {
+			total += color * base;
+			continue;
+		}
+
+		vec3 l;
+		float attenuation = incoming(light_position[i],
+			light_direction[i], light_color[i], l);
+		float diffuse = max(dot(n, l), 0.0);
+		float specular = 0.0;
+
+		if(diffuse > 0.0) {
+			vec3 h = normalize(l + v);
+			specular = pow(max(dot(n, h), 0.0), SHININESS);
+		}
+
+		total += color * attenuation
+			* (diffuse * base + SPECULAR * specular);
+	}
+
+	return total;
+}
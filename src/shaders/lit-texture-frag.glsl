@@ -0,0 +1,36 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+#version 100
+precision mediump float;
+
+uniform sampler2D texture;
+
+varying vec4 texcoord;
+
+uniform int has_fog; // 0 no, 1 yes
+uniform vec4 viewport; // Left, bottom, right and top in pixels.
+varying vec2 clip; // Clip space z and w, before the squeeze.
+
+void main() {
+	// Only draw inside of the viewport, between its near and far planes
+	// (the squeeze puts beyond them in other viewports' depth).
+	if(gl_FragCoord.x < viewport.x || gl_FragCoord.y < viewport.y
+		|| gl_FragCoord.x > viewport.z || gl_FragCoord.y > viewport.w
+		|| abs(clip.x) > clip.y)
+	{
+		discard;
+	}
+
+	vec4 sampled = texture2D(texture, texcoord.xy);
+	vec4 base = vec4(sampled.rgb, sampled.a * texcoord.a);
+	vec4 out_color = vec4(shade(base.rgb), base.a);
+
+	if(has_fog == 1) {
+		gl_FragColor = mix(out_color, vec4(fog_color(), 1.0), fog_amount());
+	} else {
+		gl_FragColor = out_color;
+	}
+}
@@ -0,0 +1,98 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+#version 100
+precision mediump float;
+
+// Only the base color texture is sampled: the other glTF textures need more
+// texture units than asi_opengl can bind.
+uniform sampler2D texture;
+
+varying vec4 texcoord;
+
+uniform int has_fog; // 0 no, 1 yes
+uniform vec4 viewport; // Left, bottom, right and top in pixels.
+varying vec2 clip; // Clip space z and w, before the squeeze.
+uniform vec4 color; // The base color.
+uniform vec4 material; // Metallic, roughness, and 1 if there's a texture.
+uniform vec4 emissive;
+
+#define PI 3.14159265
+
+// Cook-Torrance lighting.
+vec3 shade_pbr(vec3 base, float metallic, float roughness) {
+	vec3 n = normalize(world_normal);
+	vec3 v = normalize(eye.xyz - world);
+	float n_dot_v = max(dot(n, v), 0.0001);
+	float a = roughness * roughness;
+	float a2 = a * a;
+	float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+	vec3 f0 = mix(vec3(0.04), base, metallic);
+	vec3 total = vec3(0.0);
+
+	for(int i = 0; i < MAX_LIGHTS; i++) {
+		if(i >= light_count) {
+			break;
+		}
+
+		vec3 color = light_color[i].rgb;
+
+		if(light_position[i].w < 0.5) {
+			total += color * base;
+			continue;
+		}
+
+		vec3 l;
+		float attenuation = incoming(light_position[i],
+			light_direction[i], light_color[i], l);
+		float n_dot_l = dot(n, l);
+		if(n_dot_l <= 0.0) {
+			continue;
+		}
+
+		vec3 h = normalize(l + v);
+		float n_dot_h = max(dot(n, h), 0.0);
+		float v_dot_h = max(dot(v, h), 0.0);
+
+		// Distribution (GGX), geometry (Smith) and Fresnel (Schlick).
+		float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+		d = a2 / (PI * d * d);
+		float g = n_dot_v / (n_dot_v * (1.0 - k) + k)
+			* n_dot_l / (n_dot_l * (1.0 - k) + k);
+		vec3 f = f0 + (1.0 - f0) * pow(1.0 - v_dot_h, 5.0);
+		vec3 specular = d * g * f / (4.0 * n_dot_l * n_dot_v);
+		vec3 diffuse = (1.0 - f) * (1.0 - metallic) * base / PI;
+
+		total += color * attenuation * n_dot_l * (diffuse + specular);
+	}
+
+	return total;
+}
+
+void main() {
+	// Only draw inside of the viewport, between its near and far planes
+	// (the squeeze puts beyond them in other viewports' depth).
+	if(gl_FragCoord.x < viewport.x || gl_FragCoord.y < viewport.y
+		|| gl_FragCoord.x > viewport.z || gl_FragCoord.y > viewport.w
+		|| abs(clip.x) > clip.y)
+	{
+		discard;
+	}
+
+	vec4 base = color;
+	if(material.z > 0.5) {
+		vec4 sampled = texture2D(texture, texcoord.xy);
+		base *= vec4(sampled.rgb, sampled.a * texcoord.a);
+	}
+
+	vec4 out_color = vec4(shade_pbr(base.rgb, material.x, material.y)
+		+ emissive.rgb, base.a);
+
+	if(has_fog == 1) {
+		gl_FragColor = mix(out_color, vec4(fog_color(), 1.0), fog_amount());
+	} else {
+		gl_FragColor = out_color;
+	}
+}
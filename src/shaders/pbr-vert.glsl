@@ -0,0 +1,58 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+#version 100
+precision mediump float;
+
+attribute vec4 position;
+attribute vec4 texpos;
+attribute vec4 normal;
+
+uniform mat4 models_tfm; // The Models' Transform Matrix
+uniform int has_camera; // 0 no, 1 yes, 2 fog
+uniform mat4 matrix; // The Camera's Transform & Projection Matrix
+uniform mat4 squeeze; // Into the viewport, after finding z
+uniform vec4 eye; // Where the camera is in the world.
+
+varying vec4 texcoord;
+varying float z;
+varying float height; // Height in the world, for height fog.
+varying vec2 clip; // Clip space z and w, before the squeeze.
+varying vec2 around; // World x and z from the camera, for the sky.
+varying vec3 world; // Position in the world, for lighting.
+varying vec3 world_normal;
+
+// Transform a normal by the inverse-transpose of models_tfm (its cofactors,
+// flipped if it mirrors), so it stays perpendicular to a stretched surface.
+vec3 transform_normal(vec3 n) {
+	vec3 a = models_tfm[0].xyz;
+	vec3 b = models_tfm[1].xyz;
+	vec3 c = models_tfm[2].xyz;
+	vec3 cofactors = mat3(cross(b, c), cross(c, a), cross(a, b)) * n;
+
+	return dot(a, cross(b, c)) < 0.0 ? -cofactors : cofactors;
+}
+
+void main() {
+	vec4 place = models_tfm * vec4(position.xyz, 1.0);
+	height = -place.y; // +Y is down.
+	around = place.xz - eye.xz;
+
+	world = place.xyz;
+	world_normal = transform_normal(normal.xyz);
+
+	if(has_camera == 1) {
+		place = matrix * place;
+	}
+	z = length(place.xyz); // Before the viewport squeezes it.
+	clip = vec2(0.0, 1.0);
+	if(has_camera == 1) {
+		clip = place.zw;
+		place = squeeze * place;
+	}
+
+	gl_Position = vec4(place.x, -place.y, place.z, place.w);
+	texcoord = texpos;
+}
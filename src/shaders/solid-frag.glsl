@@ -7,18 +7,22 @@
 precision mediump float;
 
 uniform int has_fog; // 0 no, 1 yes
-uniform vec4 fog; // The fog color.
-uniform vec2 range; // The range of fog (fog to far clip)
+uniform vec4 viewport; // Left, bottom, right and top in pixels.
+varying vec2 clip; // Clip space z and w, before the squeeze.
 uniform vec4 color;
 
-varying float z;
-
 void main() {
+	// Only draw inside of the viewport, between its near and far planes
+	// (the squeeze puts beyond them in other viewports' depth).
+	if(gl_FragCoord.x < viewport.x || gl_FragCoord.y < viewport.y
+		|| gl_FragCoord.x > viewport.z || gl_FragCoord.y > viewport.w
+		|| abs(clip.x) > clip.y)
+	{
+		discard;
+	}
+
 	if(has_fog == 1) {
-		// Fog Calculation
-		float linear = clamp((z-range.x) / range.y, 0.0, 1.0);
-		float curved = linear * linear * linear;
-		gl_FragColor = mix(color, fog, curved);
+		gl_FragColor = mix(color, vec4(fog_color(), 1.0), fog_amount());
 	} else {
 		gl_FragColor = color;
 	}
@@ -0,0 +1,231 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Shadow maps for `set_shadows()`.  Each directional and spot light gets a
+//! depth map of the opaque shapes that cast shadows, drawn from the light,
+//! which the lit styles look up with percentage-closer filtering.
+
+use adi_gpu_base::*;
+use asi_opengl::Topology;
+
+use camera::Camera;
+use light::Light;
+use math;
+use projection::Projection;
+use raster::{self, Attributes, Frame, Uniforms};
+use STYLE_SOLID;
+
+// Near and far planes for spot lights without a range.
+const SPOT_NEAR: f32 = 0.05;
+const SPOT_FAR: f32 = 1000.0;
+
+/// Shadow settings, for `set_shadows()`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Shadows {
+	/// The width and height of each light's shadow map, in texels.
+	pub resolution: u16,
+	/// How much farther than the shadow map a surface must be to be in
+	/// shadow, in depth (0 to 1), to keep surfaces from shadowing
+	/// themselves.
+	pub bias: f32,
+	/// How many texels around each lookup to average, for soft edges.  0
+	/// for hard edges.
+	pub pcf: u8,
+}
+
+/// A shape that casts shadows: its transform, vertices, topology and fans.
+pub type Caster<'a> = (Transform, &'a [f32], Topology, &'a [(u32, u32)]);
+
+/// A light's shadow map.
+pub struct ShadowMap {
+	matrix: Transform, // World space to the light's clip space.
+	size: usize,
+	depth: Vec<f32>,
+	bias: f32,
+	pcf: isize,
+}
+
+impl ShadowMap {
+	/// Draw the shadow map for a light, or `None` if the light doesn't
+	/// cast shadows.
+	pub fn new(light: &Light, shadows: &Shadows, casters: &[Caster])
+		-> Option<ShadowMap>
+	{
+		let matrix = match *light {
+			Light::Directional { direction, .. } => {
+				directional(direction, casters)?
+			}
+			Light::Spot { position, direction, range, angle, .. } => {
+				let view = Camera::LookAt {
+					eye: position,
+					target: vec3!(position.x + direction.x,
+						position.y + direction.y,
+						position.z + direction.z),
+					up: up(direction),
+				}.transform();
+				let lens = Projection::Perspective {
+					fov: 2.0 * angle,
+					near: SPOT_NEAR,
+					far: if range > 0.0 { range } else { SPOT_FAR },
+					reversed_z: false,
+				};
+
+				view.m(lens.transform(1.0).0)
+			}
+			_ => return None,
+		};
+
+		let wh = (shadows.resolution, shadows.resolution);
+		let mut frame = Frame::new(wh);
+		let mut uniforms = Uniforms {
+			models_tfm: Transform::IDENTITY,
+			matrix: Some(matrix),
+			squeeze: Transform::IDENTITY,
+			fog: None,
+			sky: None,
+			alpha: 1.0,
+			color: [1.0, 1.0, 1.0, 1.0],
+			texture: None,
+			depth_test: true,
+			reversed_z: false,
+			viewport: None,
+			lights: &[],
+			eye: [0.0, 0.0, 0.0],
+			shadows: &[],
+			material: None,
+		};
+
+		for &(transform, vertices, topology, fans) in casters.iter() {
+			let attributes = Attributes {
+				position: vertices,
+				texpos: None,
+				acolor: None,
+				normal: None,
+				tangent: None,
+			};
+
+			uniforms.models_tfm = transform;
+			raster::draw(&mut frame, STYLE_SOLID, &uniforms,
+				&attributes, topology, fans);
+		}
+
+		Some(ShadowMap {
+			matrix,
+			size: shadows.resolution as usize,
+			depth: frame.depth().to_vec(),
+			bias: shadows.bias,
+			pcf: shadows.pcf as isize,
+		})
+	}
+
+	/// How much of the light reaches `world`, from 0 (in shadow) to 1.
+	pub fn visibility(&self, world: [f32; 3]) -> f32 {
+		let p = self.matrix.0 * vec4!(world[0], world[1], world[2], 1.0);
+
+		if p.w <= ::std::f32::EPSILON {
+			return 1.0;
+		}
+
+		// Find the texel like the rasterizer does, which flips y like
+		// the vertex shaders.
+		let size = self.size as f32;
+		let x = ((p.x / p.w + 1.0) * 0.5 * size).floor() as isize;
+		let y = ((-p.y / p.w + 1.0) * 0.5 * size).floor() as isize;
+		let depth = (p.z / p.w) * 0.5 + 0.5;
+
+		// The map is only as deep as the casters, so a receiver behind
+		// all of them is in shadow wherever anything was drawn.
+		let (depth, bias) = if depth > 1.0 {
+			(1.0, 0.0)
+		} else {
+			(depth, self.bias)
+		};
+
+		let mut lit = 0;
+		let mut count = 0;
+		for dy in -self.pcf..self.pcf + 1 {
+			for dx in -self.pcf..self.pcf + 1 {
+				let (sx, sy) = (x + dx, y + dy);
+				count += 1;
+
+				// Nothing casts shadows outside of the map.
+				if sx < 0 || sy < 0 || sx >= self.size as isize
+					|| sy >= self.size as isize
+				{
+					lit += 1;
+					continue;
+				}
+
+				// Rows are stored top to bottom.
+				let index = (self.size - 1 - sy as usize) * self.size
+					+ sx as usize;
+				if depth - bias <= self.depth[index] {
+					lit += 1;
+				}
+			}
+		}
+
+		lit as f32 / count as f32
+	}
+}
+
+// Get the matrix for a directional light, an orthographic projection fitted
+// around the casters.  `None` if nothing casts shadows.
+fn directional(direction: Vec3, casters: &[Caster]) -> Option<Transform> {
+	let view = Camera::LookAt {
+		eye: vec3!(0.0, 0.0, 0.0),
+		target: direction,
+		up: up(direction),
+	}.transform();
+	let mut min = [::std::f32::MAX; 3];
+	let mut max = [::std::f32::MIN; 3];
+
+	for &(transform, vertices, _, _) in casters.iter() {
+		for v in vertices.chunks(4) {
+			let p = view.0 * (transform.0
+				* vec4!(v[0], v[1], v[2], 1.0));
+			let p = [p.x, p.y, p.z];
+
+			for i in 0..3 {
+				min[i] = min[i].min(p[i]);
+				max[i] = max[i].max(p[i]);
+			}
+		}
+	}
+
+	if min[0] > max[0] {
+		return None;
+	}
+
+	// Pad the bounds, so the casters at the edges aren't clipped.
+	let mut scale = [0.0; 3];
+	let mut offset = [0.0; 3];
+	for i in 0..3 {
+		let pad = (max[i] - min[i]) * 0.01 + 0.001;
+		let (lo, hi) = (min[i] - pad, max[i] + pad);
+
+		scale[i] = 2.0 / (hi - lo);
+		offset[i] = -(hi + lo) / (hi - lo);
+	}
+
+	// Matrices are written column by column.
+	Some(view.m(mat4!(
+		scale[0], 0.0, 0.0, 0.0,
+		0.0, scale[1], 0.0, 0.0,
+		0.0, 0.0, scale[2], 0.0,
+		offset[0], offset[1], offset[2], 1.0
+	)))
+}
+
+// Pick an up direction that isn't parallel to `direction`.
+fn up(direction: Vec3) -> Vec3 {
+	let length = math::length([direction.x, direction.y, direction.z]);
+
+	if direction.y.abs() > length * 0.99 {
+		vec3!(0.0, 0.0, 1.0)
+	} else {
+		vec3!(0.0, 1.0, 0.0)
+	}
+}
@@ -0,0 +1,213 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! A list of shapes drawn in sorted order, reusing slots of dropped shapes.
+
+use adi_gpu_base::{Point, Vec3};
+
+use handle;
+use Error;
+
+pub struct Shapes<T> {
+	// Id of the display the shapes are on.
+	display: usize,
+	// Generation of each slot, and the shape.  `None` if dropped.
+	slots: Vec<(usize, Option<T>)>,
+	// Indices of the shapes, in the order to draw them.
+	order: Vec<u32>,
+	// Indices of empty slots, to be reused.
+	free: Vec<u32>,
+	// When the shape in each slot was made, counting up from 0.
+	made: Vec<u64>,
+	// When the next shape will be made.
+	next: u64,
+}
+
+impl<T> Shapes<T> {
+	pub fn new(display: usize) -> Shapes<T> {
+		Shapes {
+			display, slots: vec![], order: vec![], free: vec![],
+			made: vec![], next: 0,
+		}
+	}
+
+	/// Add a shape, returning its handle.
+	pub fn push(&mut self, shape: T) -> u32 {
+		let index = if let Some(index) = self.free.pop() {
+			self.slots[index as usize].1 = Some(shape);
+			self.made[index as usize] = self.next;
+			index
+		} else {
+			self.slots.push((0, Some(shape)));
+			self.made.push(self.next);
+			self.slots.len() as u32 - 1
+		};
+
+		self.next += 1;
+
+		self.order.push(index);
+		handle::pack(index as usize, self.display,
+			self.slots[index as usize].0) as u32
+	}
+
+	/// Remove a shape, freeing its slot.
+	pub fn remove(&mut self, handle: u32) -> Result<T, Error> {
+		let index = match self.index(handle) {
+			Err(Error::StaleHandle) => return Err(Error::DoubleDrop),
+			index => index?,
+		};
+		let slot = &mut self.slots[index];
+		let shape = slot.1.take().unwrap();
+		let position = self.order.iter()
+			.position(|i| *i as usize == index)
+			.unwrap();
+
+		slot.0 = handle::next_generation(slot.0, ::std::u32::MAX as usize);
+		self.order.remove(position);
+		self.free.push(index as u32);
+
+		Ok(shape)
+	}
+
+	/// Get a shape for modifying.
+	pub fn get_mut(&mut self, handle: u32) -> Result<&mut T, Error> {
+		let index = self.index(handle)?;
+
+		Ok(self.slots[index].1.as_mut().unwrap())
+	}
+
+	/// Sort the draw order by a key, then by the order the shapes were
+	/// made.
+	pub fn sort_by_key<K: Ord, F: Fn(&T) -> K>(&mut self, key: F) {
+		let slots = &self.slots;
+		let made = &self.made;
+
+		self.order.sort_by_key(|i| (key(slots[*i as usize].1.as_ref()
+			.unwrap()), made[*i as usize]));
+	}
+
+	/// Iterate over the shapes in draw order.
+	pub fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T> + 'a {
+		let slots = &self.slots;
+
+		self.order.iter().map(move |i| slots[*i as usize].1.as_ref()
+			.unwrap())
+	}
+
+	/// Iterate over the shapes in draw order, only those in `handles` if
+	/// it's `Some`.  Handles to dropped shapes are skipped.
+	pub fn iter_in<'a>(&'a self, handles: Option<&[u32]>)
+		-> impl Iterator<Item = &'a T> + 'a
+	{
+		let slots = &self.slots;
+		let indices: Option<Vec<usize>> = handles.map(|handles| {
+			handles.iter().filter_map(|h| self.index(*h).ok())
+				.collect()
+		});
+
+		self.order.iter()
+			.filter(move |i| match indices {
+				Some(ref indices) => indices.contains(&(**i as usize)),
+				None => true,
+			})
+			.map(move |i| slots[*i as usize].1.as_ref().unwrap())
+	}
+
+	// Get the slot index of a handle, if it refers to a live shape.
+	fn index(&self, handle: u32) -> Result<usize, Error> {
+		let (index, generation) = handle::unpack(handle as usize,
+			self.display)?;
+		let slot = self.slots.get(index).ok_or(Error::InvalidHandle)?;
+
+		if slot.1.is_none() || slot.0 != generation {
+			return Err(Error::StaleHandle);
+		}
+
+		Ok(index)
+	}
+}
+
+impl<T: Point> Shapes<T> {
+	/// Sort the draw order by distance from `xyz`, nearest first if
+	/// `nearest` is true, otherwise farthest first.
+	pub fn sort(&mut self, nearest: bool, xyz: Vec3) {
+		let slots = &self.slots;
+		let distance = |i: &u32| {
+			let p = slots[*i as usize].1.as_ref().unwrap().point();
+			let (x, y, z) = (p.x - xyz.x, p.y - xyz.y, p.z - xyz.z);
+
+			x * x + y * y + z * z
+		};
+
+		self.order.sort_by(|a, b| {
+			let order = distance(a).partial_cmp(&distance(b))
+				.unwrap_or(::std::cmp::Ordering::Equal);
+
+			if nearest { order } else { order.reverse() }
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn generations() {
+		let mut shapes = Shapes::new(1);
+		let a = shapes.push('a');
+
+		assert_eq!(shapes.remove(a), Ok('a'));
+		assert_eq!(shapes.remove(a), Err(Error::DoubleDrop));
+
+		// The slot is reused, but the old handle stays stale.
+		let b = shapes.push('b');
+		assert_ne!(a, b);
+		assert_eq!(shapes.get_mut(a), Err(Error::StaleHandle));
+		assert_eq!(shapes.get_mut(b), Ok(&mut 'b'));
+	}
+
+	#[test]
+	fn order() {
+		let mut shapes = Shapes::new(1);
+		let a = shapes.push(3);
+		let b = shapes.push(1);
+		let c = shapes.push(2);
+
+		shapes.sort_by_key(|x| *x);
+		assert_eq!(shapes.iter().cloned().collect::<Vec<_>>(),
+			vec![1, 2, 3]);
+
+		shapes.remove(b).unwrap();
+		assert_eq!(shapes.iter_in(Some(&[a, b, c])).cloned()
+			.collect::<Vec<_>>(), vec![2, 3]);
+		assert_eq!(shapes.iter_in(Some(&[a])).cloned()
+			.collect::<Vec<_>>(), vec![3]);
+	}
+
+	#[test]
+	fn order_made() {
+		let mut shapes = Shapes::new(1);
+		let a = shapes.push((0, 'a'));
+		let b = shapes.push((0, 'b'));
+		let by_key = |shapes: &mut Shapes<(i32, char)>| {
+			shapes.sort_by_key(|x| x.0);
+			shapes.iter().map(|x| x.1).collect::<String>()
+		};
+
+		// Moving `a` up and back down puts it back before `b`.
+		shapes.get_mut(a).unwrap().0 = 1;
+		assert_eq!(by_key(&mut shapes), "ba");
+		shapes.get_mut(a).unwrap().0 = 0;
+		assert_eq!(by_key(&mut shapes), "ab");
+
+		// A shape made in a reused slot is still made last.
+		shapes.remove(a).unwrap();
+		shapes.push((0, 'c'));
+		assert_eq!(by_key(&mut shapes), "bc");
+		shapes.get_mut(b).unwrap().0 = 0;
+		assert_eq!(by_key(&mut shapes), "bc");
+	}
+}
@@ -0,0 +1,397 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! 2D vector paths, tessellated for the vector graphics style.
+//!
+//! Curves are drawn with the Loop-Blinn method: each quadratic bezier gets a
+//! triangle made of its end points and control point, with texture
+//! coordinates (0, 0), (0.5, 0) and (1, 1).  The fragment shader then keeps
+//! the side of `u² - v = 0` that's inside the path, so curves stay smooth at
+//! any scale.
+
+// How many quadratic beziers a cubic bezier is split into.
+const CUBIC_SPLITS: usize = 4;
+// How many line segments a curve is split into for strokes.
+const STROKE_SPLITS: usize = 16;
+
+#[derive(Copy, Clone)]
+enum Segment {
+	Line([f32; 2]),
+	Quad([f32; 2], [f32; 2]),
+}
+
+struct Contour {
+	start: [f32; 2],
+	segments: Vec<Segment>,
+}
+
+/// A 2D path made of lines and bezier curves, which is either filled or
+/// stroked.  Each contour is filled on its own, so contours shouldn't
+/// overlap (there are no holes).
+#[derive(Default)]
+pub struct Path {
+	contours: Vec<Contour>,
+	stroke: Option<f32>,
+}
+
+impl Path {
+	/// Create an empty path.
+	pub fn new() -> Path {
+		Path::default()
+	}
+
+	/// Start a new contour at (`x`, `y`).
+	pub fn move_to(mut self, x: f32, y: f32) -> Path {
+		self.contours.push(Contour { start: [x, y], segments: vec![] });
+		self
+	}
+
+	/// Add a line to (`x`, `y`).
+	pub fn line_to(mut self, x: f32, y: f32) -> Path {
+		self.segment(Segment::Line([x, y]));
+		self
+	}
+
+	/// Add a quadratic bezier curve with control point (`cx`, `cy`), to
+	/// (`x`, `y`).
+	pub fn quad_to(mut self, cx: f32, cy: f32, x: f32, y: f32) -> Path {
+		self.segment(Segment::Quad([cx, cy], [x, y]));
+		self
+	}
+
+	/// Add a cubic bezier curve with control points (`c1x`, `c1y`) and
+	/// (`c2x`, `c2y`), to (`x`, `y`).  It's approximated with quadratic
+	/// bezier curves.
+	pub fn cubic_to(mut self, c1x: f32, c1y: f32, c2x: f32, c2y: f32,
+		x: f32, y: f32) -> Path
+	{
+		let p0 = self.end();
+		let cubic = [p0, [c1x, c1y], [c2x, c2y], [x, y]];
+
+		for i in 0..CUBIC_SPLITS {
+			let t0 = i as f32 / CUBIC_SPLITS as f32;
+			let t1 = (i + 1) as f32 / CUBIC_SPLITS as f32;
+			let a = cubic_point(&cubic, t0);
+			let b = cubic_point(&cubic, t1);
+			// Control point where the tangents at both ends meet,
+			// approximated by the midpoint of the two estimates.
+			let da = cubic_tangent(&cubic, t0);
+			let db = cubic_tangent(&cubic, t1);
+			let dt = (t1 - t0) / 2.0;
+			let c = [
+				(a[0] + da[0] * dt + b[0] - db[0] * dt) / 2.0,
+				(a[1] + da[1] * dt + b[1] - db[1] * dt) / 2.0,
+			];
+
+			self.segment(Segment::Quad(c, b));
+		}
+
+		self
+	}
+
+	/// Close the current contour with a line back to its start.
+	pub fn close(mut self) -> Path {
+		if let Some(contour) = self.contours.last_mut() {
+			let start = contour.start;
+			contour.segments.push(Segment::Line(start));
+		}
+		self
+	}
+
+	/// Draw the outline of the path, `width` wide, instead of filling it.
+	pub fn stroke(mut self, width: f32) -> Path {
+		self.stroke = Some(width);
+		self
+	}
+
+	// Add a segment to the current contour, starting one if needed.
+	fn segment(&mut self, segment: Segment) {
+		if self.contours.is_empty() {
+			self.contours.push(Contour {
+				start: [0.0, 0.0], segments: vec![]
+			});
+		}
+		self.contours.last_mut().unwrap().segments.push(segment);
+	}
+
+	// Where the current contour ends.
+	fn end(&self) -> [f32; 2] {
+		match self.contours.last() {
+			Some(contour) => match contour.segments.last() {
+				Some(&Segment::Line(p)) | Some(&Segment::Quad(_, p))
+					=> p,
+				None => contour.start,
+			},
+			None => [0.0, 0.0],
+		}
+	}
+
+	// Tessellate the path into a triangle list of vertices and texture
+	// coordinates for the vector graphics style.
+	pub(crate) fn tessellate(&self) -> (Vec<f32>, Vec<f32>) {
+		let mut mesh = Mesh { vertices: vec![], texcoords: vec![] };
+
+		for contour in self.contours.iter() {
+			if let Some(width) = self.stroke {
+				stroke(&mut mesh, contour, width);
+			} else {
+				fill(&mut mesh, contour);
+			}
+		}
+
+		(mesh.vertices, mesh.texcoords)
+	}
+}
+
+// Triangles, with Loop-Blinn texture coordinates (u, v, kind).  Kind is 0 for
+// solid triangles, 1 to fill inside the curve, and -1 to fill outside of it.
+struct Mesh {
+	vertices: Vec<f32>,
+	texcoords: Vec<f32>,
+}
+
+impl Mesh {
+	fn triangle(&mut self, mut p: [[f32; 2]; 3], mut uv: [[f32; 3]; 3]) {
+		let area = (p[1][0] - p[0][0]) * (p[2][1] - p[0][1])
+			- (p[2][0] - p[0][0]) * (p[1][1] - p[0][1]);
+
+		if area == 0.0 {
+			return;
+		}
+
+		// The vertex shaders flip y, so wind clockwise here for the
+		// triangle to face forward.
+		if area > 0.0 {
+			p.swap(1, 2);
+			uv.swap(1, 2);
+		}
+
+		for i in 0..3 {
+			self.vertices.extend_from_slice(&[p[i][0], p[i][1], 0.0,
+				1.0]);
+			self.texcoords.extend_from_slice(&[uv[i][0], uv[i][1],
+				uv[i][2], 1.0]);
+		}
+	}
+}
+
+fn fill(mesh: &mut Mesh, contour: &Contour) {
+	// Orientation of the contour, for telling which side curves bulge to.
+	let mut area = 0.0;
+	let mut a = contour.start;
+	for segment in contour.segments.iter() {
+		let b = match *segment {
+			Segment::Line(b) | Segment::Quad(_, b) => b,
+		};
+		area += a[0] * b[1] - b[0] * a[1];
+		a = b;
+	}
+
+	let mut polygon = vec![contour.start];
+	let mut a = contour.start;
+	for segment in contour.segments.iter() {
+		match *segment {
+			Segment::Line(b) => {
+				polygon.push(b);
+				a = b;
+			}
+			Segment::Quad(c, b) => {
+				let bulge = (b[0] - a[0]) * (c[1] - a[1])
+					- (b[1] - a[1]) * (c[0] - a[0]);
+				let outward = (bulge < 0.0) == (area > 0.0);
+
+				// Curves bulging out are filled inside, and curves
+				// bulging in have their control point in the polygon
+				// and are filled outside.
+				let kind = if outward { 1.0 } else { -1.0 };
+				if !outward {
+					polygon.push(c);
+				}
+				polygon.push(b);
+
+				mesh.triangle([a, c, b], [
+					[0.0, 0.0, kind],
+					[0.5, 0.0, kind],
+					[1.0, 1.0, kind],
+				]);
+				a = b;
+			}
+		}
+	}
+
+	triangulate(mesh, polygon);
+}
+
+// Ear clipping, for simple polygons.
+fn triangulate(mesh: &mut Mesh, mut polygon: Vec<[f32; 2]>) {
+	if polygon.len() > 1 && polygon.first() == polygon.last() {
+		polygon.pop();
+	}
+
+	// Make the polygon counter-clockwise.
+	let area = (0..polygon.len()).fold(0.0, |sum, i| {
+		let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+		sum + a[0] * b[1] - b[0] * a[1]
+	});
+	if area < 0.0 {
+		polygon.reverse();
+	}
+
+	let solid = [[0.0, 0.0, 0.0]; 3];
+
+	while polygon.len() > 3 {
+		let n = polygon.len();
+		let ear = (0..n).find(|&i| {
+			let a = polygon[(i + n - 1) % n];
+			let b = polygon[i];
+			let c = polygon[(i + 1) % n];
+
+			cross(a, b, c) > 0.0 && !polygon.iter().any(|&p| {
+				p != a && p != b && p != c
+					&& inside(p, a, b, c)
+			})
+		});
+
+		// Not a simple polygon, or only degenerate ears left.
+		let i = match ear {
+			Some(i) => i,
+			None => break,
+		};
+
+		mesh.triangle([polygon[(i + n - 1) % n], polygon[i],
+			polygon[(i + 1) % n]], solid);
+		polygon.remove(i);
+	}
+
+	if polygon.len() == 3 {
+		mesh.triangle([polygon[0], polygon[1], polygon[2]], solid);
+	}
+}
+
+fn stroke(mesh: &mut Mesh, contour: &Contour, width: f32) {
+	let solid = [[0.0, 0.0, 0.0]; 3];
+	let half = width / 2.0;
+
+	let mut points = vec![contour.start];
+	for segment in contour.segments.iter() {
+		match *segment {
+			Segment::Line(b) => points.push(b),
+			Segment::Quad(c, b) => {
+				let a = *points.last().unwrap();
+				for i in 1..STROKE_SPLITS + 1 {
+					let t = i as f32 / STROKE_SPLITS as f32;
+					points.push(quad_point(a, c, b, t));
+				}
+			}
+		}
+	}
+
+	for line in points.windows(2) {
+		let (a, b) = (line[0], line[1]);
+		let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+		let length = (dx * dx + dy * dy).sqrt();
+
+		if length == 0.0 {
+			continue;
+		}
+
+		let n = [-dy / length * half, dx / length * half];
+		let quad = [
+			[a[0] + n[0], a[1] + n[1]],
+			[b[0] + n[0], b[1] + n[1]],
+			[b[0] - n[0], b[1] - n[1]],
+			[a[0] - n[0], a[1] - n[1]],
+		];
+
+		mesh.triangle([quad[0], quad[1], quad[2]], solid);
+		mesh.triangle([quad[0], quad[2], quad[3]], solid);
+	}
+}
+
+fn cross(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+	(b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn inside(p: [f32; 2], a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> bool {
+	cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+fn quad_point(a: [f32; 2], c: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+	let s = 1.0 - t;
+
+	[
+		s * s * a[0] + 2.0 * s * t * c[0] + t * t * b[0],
+		s * s * a[1] + 2.0 * s * t * c[1] + t * t * b[1],
+	]
+}
+
+fn cubic_point(p: &[[f32; 2]; 4], t: f32) -> [f32; 2] {
+	let s = 1.0 - t;
+	let w = [s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t];
+
+	[
+		w[0] * p[0][0] + w[1] * p[1][0] + w[2] * p[2][0] + w[3] * p[3][0],
+		w[0] * p[0][1] + w[1] * p[1][1] + w[2] * p[2][1] + w[3] * p[3][1],
+	]
+}
+
+fn cubic_tangent(p: &[[f32; 2]; 4], t: f32) -> [f32; 2] {
+	let s = 1.0 - t;
+	let w = [
+		-3.0 * s * s,
+		3.0 * s * s - 6.0 * s * t,
+		6.0 * s * t - 3.0 * t * t,
+		3.0 * t * t,
+	];
+
+	[
+		w[0] * p[0][0] + w[1] * p[1][0] + w[2] * p[2][0] + w[3] * p[3][0],
+		w[0] * p[0][1] + w[1] * p[1][1] + w[2] * p[2][1] + w[3] * p[3][1],
+	]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Signed area of each triangle, negative for clockwise.
+	fn areas(vertices: &[f32]) -> Vec<f32> {
+		vertices.chunks(12).map(|v| {
+			cross([v[0], v[1]], [v[4], v[5]], [v[8], v[9]]) / 2.0
+		}).collect()
+	}
+
+	#[test]
+	fn fill() {
+		let (vertices, texcoords) = Path::new().move_to(0.0, 0.0)
+			.line_to(1.0, 0.0).line_to(1.0, 1.0).line_to(0.0, 1.0)
+			.close().tessellate();
+		let areas = areas(&vertices);
+
+		assert_eq!(areas.len(), 2);
+		assert!(areas.iter().all(|a| *a < 0.0));
+		assert_eq!(areas.iter().sum::<f32>(), -1.0);
+		assert!(texcoords.chunks(4).all(|uv| uv[2] == 0.0));
+	}
+
+	#[test]
+	fn curve() {
+		let (vertices, texcoords) = Path::new().move_to(0.0, 0.0)
+			.quad_to(0.5, 1.0, 1.0, 0.0).close().tessellate();
+
+		assert_eq!(vertices.len(), 12);
+		assert!(texcoords.chunks(4).all(|uv| uv[2] == 1.0));
+	}
+
+	#[test]
+	fn stroke() {
+		let (vertices, _) = Path::new().move_to(0.0, 0.0)
+			.line_to(2.0, 0.0).stroke(1.0).tessellate();
+		let areas = areas(&vertices);
+
+		assert_eq!(areas.len(), 2);
+		assert_eq!(areas.iter().sum::<f32>(), -2.0);
+	}
+}
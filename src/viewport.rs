@@ -0,0 +1,82 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Viewports for `Display::set_viewports()`.
+//!
+//! `asi_opengl` can only set a viewport covering the whole window, and has no
+//! scissor test, so each viewport's `squeeze` matrix moves the scene into its
+//! part of the window (after the fog distance is found), and the fragment
+//! shaders discard anything outside of it.  Viewports also get their own
+//! slice of the depth buffer, later ones nearer, so a picture-in-picture
+//! viewport is drawn over the ones before it.  OpenGL only clips to the
+//! whole depth buffer, so the fragment shaders also discard anything beyond
+//! the near and far planes before the squeeze, which would otherwise land in
+//! another viewport's slice.
+
+use adi_gpu_base::*;
+
+use camera::Camera;
+use projection::{self, Projection};
+
+/// A part of the window that the opaque and alpha shapes are drawn into,
+/// with its own camera.
+#[derive(Copy, Clone)]
+pub struct Viewport {
+	/// Left, top, width and height, as fractions of the window (0 to 1).
+	pub rect: [f32; 4],
+	/// Where the viewport's camera is and which way it faces.
+	pub camera: Camera,
+}
+
+impl Viewport {
+	/// Create a viewport from a rectangle (left, top, width and height as
+	/// fractions of the window) and a camera.
+	pub fn new(rect: [f32; 4], camera: Camera) -> Viewport {
+		Viewport { rect, camera }
+	}
+
+	// Get the camera and projection matrix, on a `wh` window.
+	pub(crate) fn transform(&self, lens: Option<Projection>, wh: (u16, u16))
+		-> Transform
+	{
+		let ar = (self.rect[2] * wh.0 as f32) / (self.rect[3] * wh.1 as f32);
+
+		self.camera.transform().m(projection::transform(lens, ar).0)
+	}
+
+	// Get the matrix that squeezes clip space into viewport `i` of `n`.
+	pub(crate) fn squeeze(&self, lens: Option<Projection>, i: usize,
+		n: usize) -> Transform
+	{
+		let (x, y, w, h) = (self.rect[0], self.rect[1], self.rect[2],
+			self.rect[3]);
+		// Depth slice, from far to near as `i` increases.
+		let reversed_z = lens.map(|l| l.reversed_z()).unwrap_or(false);
+		let slice = if reversed_z { n - 1 - i } else { i };
+		let depth = 1.0 / n as f32;
+		let near = 1.0 - 2.0 * (slice + 1) as f32 * depth;
+
+		// Scale and move clip space into the rectangle.  The vertex
+		// shaders flip y, so the top of the window is at -1 here.
+		// Matrices are written column by column.
+		Transform(mat4!(
+			w, 0.0, 0.0, 0.0,
+			0.0, h, 0.0, 0.0,
+			0.0, 0.0, depth, 0.0,
+			2.0 * x + w - 1.0, 2.0 * y + h - 1.0, near + depth, 1.0
+		))
+	}
+
+	// Get the rectangle in pixels: left, top, width and height.
+	pub(crate) fn pixels(&self, wh: (u16, u16)) -> (u16, u16, u16, u16) {
+		let (w, h) = (wh.0 as f32, wh.1 as f32);
+		let (x0, y0) = (self.rect[0] * w, self.rect[1] * h);
+		let (x1, y1) = (x0 + self.rect[2] * w, y0 + self.rect[3] * h);
+
+		(x0.round() as u16, y0.round() as u16,
+			(x1.round() - x0.round()) as u16,
+			(y1.round() - y0.round()) as u16)
+	}
+}
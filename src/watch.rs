@@ -0,0 +1,42 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Shader hot-reloading for `Display::watch_shaders()`.  There's no file
+//! watching in `std`, so the modification times are checked every frame.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+pub struct Watch {
+	dir: PathBuf,
+	// When each file was last seen modified.
+	modified: HashMap<&'static str, SystemTime>,
+}
+
+impl Watch {
+	pub fn new(dir: PathBuf) -> Watch {
+		Watch { dir, modified: HashMap::new() }
+	}
+
+	/// Check if a file has been modified since the last check.  Files that
+	/// don't exist never count as modified.
+	pub fn modified(&mut self, file: &'static str) -> bool {
+		let time = match fs::metadata(self.dir.join(file))
+			.and_then(|m| m.modified())
+		{
+			Ok(time) => time,
+			Err(_) => return false,
+		};
+
+		self.modified.insert(file, time) != Some(time)
+	}
+
+	/// Read a file, if it exists.
+	pub fn read(&self, file: &str) -> Option<Vec<u8>> {
+		fs::read(self.dir.join(file)).ok()
+	}
+}
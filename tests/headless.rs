@@ -0,0 +1,1052 @@
+// Copyright Jeron A. Lau 2018.
+// Dual-licensed under either the MIT License or the Boost Software License,
+// Version 1.0.  (See accompanying file LICENSE_1_0.txt or copy at
+// https://www.boost.org/LICENSE_1_0.txt)
+
+//! Golden frames for each style, drawn by the `Headless` display.  Shapes are
+//! GUI shapes with no transform, so each golden frame can be worked out by
+//! hand from the shaders.
+
+extern crate adi_gpu_base as base;
+extern crate adi_gpu_opengl as gpu;
+
+use base::{Display, Transform, VFrame};
+use gpu::{Camera, Error, Fog, FogMode, Headless, Light, Material, Mesh, Path,
+	PostProcess, Projection, Shadows, Viewport};
+
+const PI: f32 = ::std::f32::consts::PI;
+
+// The frame is 4 by 4 pixels.
+const WH: (u16, u16) = (4, 4);
+
+// The clear color.
+const CLEAR: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+// A 2 by 2 texture: red, green on top, and blue, white on the bottom.
+const TEXTURE: [[f32; 4]; 4] = [
+	[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0],
+	[0.0, 0.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0],
+];
+
+// A square covering the whole frame, starting at the top left.
+const SQUARE: [f32; 16] = [
+	-1.0, -1.0, 0.0, 1.0,
+	-1.0, 1.0, 0.0, 1.0,
+	1.0, 1.0, 0.0, 1.0,
+	1.0, -1.0, 0.0, 1.0,
+];
+
+// Texture coordinates for `SQUARE`.
+const TEXCOORDS: [f32; 16] = [
+	0.0, 0.0, 1.0, 1.0,
+	0.0, 1.0, 1.0, 1.0,
+	1.0, 1.0, 1.0, 1.0,
+	1.0, 0.0, 1.0, 1.0,
+];
+
+// Normals for `SQUARE`, facing the camera.
+const NORMALS: [f32; 16] = [
+	0.0, 0.0, -1.0, 0.0,
+	0.0, 0.0, -1.0, 0.0,
+	0.0, 0.0, -1.0, 0.0,
+	0.0, 0.0, -1.0, 0.0,
+];
+
+// Pack an RGBA pixel (red in the lowest byte).
+fn pack(color: [f32; 4]) -> u32 {
+	let c = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u32;
+
+	c(color[0]) | (c(color[1]) << 8) | (c(color[2]) << 16)
+		| (c(color[3]) << 24)
+}
+
+fn mul(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+	[a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+// Make a golden frame from the color of each pixel.
+fn golden<F: Fn(usize, usize) -> [f32; 4]>(color: F) -> VFrame {
+	let mut pixels = vec![];
+
+	for y in 0..WH.1 as usize {
+		for x in 0..WH.0 as usize {
+			pixels.push(pack(color(x, y)));
+		}
+	}
+
+	VFrame(pixels)
+}
+
+// The texel of `TEXTURE` under a pixel.
+fn texel(x: usize, y: usize) -> [f32; 4] {
+	TEXTURE[(y / 2) * 2 + x / 2]
+}
+
+fn display() -> Box<Headless> {
+	let mut display = gpu::new_headless(WH);
+
+	display.color((CLEAR[0], CLEAR[1], CLEAR[2]));
+	display
+}
+
+fn texture(display: &mut Headless) -> base::Texture {
+	let pixels = TEXTURE.iter().map(|c| pack(*c)).collect();
+
+	display.texture((2, 2), &VFrame(pixels))
+}
+
+// Draw a frame, and check it against the golden frame.
+fn check(display: &mut Headless, golden: VFrame) {
+	display.update();
+
+	assert_eq!(display.diff(&golden, 1), 0, "{:08x?}",
+		display.screenshot().0);
+}
+
+#[test]
+fn solid() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	display.shape_solid(&model, Transform::IDENTITY, color, false, false,
+		false);
+	check(&mut display, golden(|_, _| color));
+}
+
+#[test]
+fn fan_past_vertices() {
+	let mut display = display();
+	// One triangle covering the frame, in a fan that says it has 5.
+	let model = display.model(&[
+		-1.0, -1.0, 0.0, 1.0,
+		-1.0, 3.0, 0.0, 1.0,
+		3.0, -1.0, 0.0, 1.0,
+	], vec![(0, 5)]);
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	display.shape_solid(&model, Transform::IDENTITY, color, false, false,
+		false);
+	check(&mut display, golden(|_, _| color));
+}
+
+#[test]
+fn gradient() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	// Red on the left, fading to green on the right.
+	let colors = display.gradient(&[
+		1.0, 0.0, 0.0, 1.0,
+		1.0, 0.0, 0.0, 1.0,
+		0.0, 1.0, 0.0, 1.0,
+		0.0, 1.0, 0.0, 1.0,
+	]);
+
+	display.shape_gradient(&model, Transform::IDENTITY, colors, false,
+		false, false);
+	check(&mut display, golden(|x, _| {
+		let s = (x as f32 + 0.5) / WH.0 as f32;
+
+		[1.0 - s, s, 0.0, 1.0]
+	}));
+}
+
+#[test]
+fn texture_style() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+
+	display.shape_texture(&model, Transform::IDENTITY, &texture, tc,
+		false, false, false);
+	check(&mut display, golden(texel));
+}
+
+#[test]
+fn faded() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+
+	display.shape_faded(&model, Transform::IDENTITY, &texture, tc, 0.25,
+		false, false);
+	check(&mut display, golden(|x, y| {
+		let c = texel(x, y);
+		let mix = |i: usize| c[i] * 0.25 + CLEAR[i] * 0.75;
+
+		// Alpha is blended like the color, with itself as the factor.
+		[mix(0), mix(1), mix(2), 0.25 * 0.25 + CLEAR[3] * 0.75]
+	}));
+}
+
+#[test]
+fn tinted() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+	let tint = [0.5, 1.0, 0.25, 1.0];
+
+	display.shape_tinted(&model, Transform::IDENTITY, &texture, tc, tint,
+		false, false, false);
+	check(&mut display, golden(|x, y| mul(texel(x, y), tint)));
+}
+
+#[test]
+fn complex() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+	let tint = [0.5, 0.5, 1.0, 1.0];
+	let tints = display.gradient(&[
+		tint[0], tint[1], tint[2], tint[3],
+		tint[0], tint[1], tint[2], tint[3],
+		tint[0], tint[1], tint[2], tint[3],
+		tint[0], tint[1], tint[2], tint[3],
+	]);
+
+	display.shape_complex(&model, Transform::IDENTITY, &texture, tc,
+		tints, false, false, false);
+	check(&mut display, golden(|x, y| mul(texel(x, y), tint)));
+}
+
+#[test]
+fn vector() {
+	let mut display = display();
+	let color = [0.0, 1.0, 0.0, 1.0];
+	// The left half of the frame.
+	let path = Path::new().move_to(-1.0, -1.0).line_to(0.0, -1.0)
+		.line_to(0.0, 1.0).line_to(-1.0, 1.0).close();
+
+	display.shape_vector(&path, Transform::IDENTITY, color, false, false,
+		false);
+	check(&mut display, golden(|x, _| if x < 2 { color } else { CLEAR }));
+}
+
+// Ambient light only, so lit shapes are their color at half brightness.
+fn lit_display() -> (Box<Headless>, base::Model) {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+
+	display.set_normals(&model, &NORMALS).unwrap();
+	display.light(&[Light::Ambient { color: [0.5, 0.5, 0.5] }]).unwrap();
+	(display, model)
+}
+
+fn half(c: [f32; 4]) -> [f32; 4] {
+	[c[0] * 0.5, c[1] * 0.5, c[2] * 0.5, c[3]]
+}
+
+#[test]
+fn lit_solid() {
+	let (mut display, model) = lit_display();
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	display.shape_lit_solid(&model, Transform::IDENTITY, color, false,
+		false, false).unwrap();
+	check(&mut display, golden(|_, _| half(color)));
+}
+
+// Where the middle of a pixel is, for shapes drawn without the camera.
+fn world(x: usize, y: usize) -> [f32; 3] {
+	[(x as f32 + 0.5) / 2.0 - 1.0, (y as f32 + 0.5) / 2.0 - 1.0, 0.0]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+	let length = (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt();
+
+	[a[0] / length, a[1] / length, a[2] / length]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+// Blinn-Phong shading (shininess 32, specular 0.5) of `base` at `world`
+// facing `n`, seen from the origin, by a white light in direction `l` with
+// `attenuation`.
+fn blinn_phong(base: [f32; 4], n: [f32; 3], world: [f32; 3], l: [f32; 3],
+	attenuation: f32) -> [f32; 4]
+{
+	let (n, l) = (normalize(n), normalize(l));
+	let v = normalize([-world[0], -world[1], -world[2]]);
+	let h = normalize([l[0] + v[0], l[1] + v[1], l[2] + v[2]]);
+	let diffuse = dot(n, l).max(0.0);
+	let specular = if diffuse > 0.0 {
+		0.5 * dot(n, h).max(0.0).powi(32)
+	} else {
+		0.0
+	};
+	let c = |i: usize| attenuation * (diffuse * base[i] + specular);
+
+	[c(0), c(1), c(2), base[3]]
+}
+
+// A lit square lit by `light`, with `transform`, normals `n` before it.
+fn lit_frame(light: Light, transform: Transform, n: [f32; 3])
+	-> (Box<Headless>, [f32; 4])
+{
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let color = [1.0, 0.5, 0.25, 1.0];
+
+	display.set_normals(&model, &[
+		n[0], n[1], n[2], 0.0,
+		n[0], n[1], n[2], 0.0,
+		n[0], n[1], n[2], 0.0,
+		n[0], n[1], n[2], 0.0,
+	]).unwrap();
+	display.light(&[light]).unwrap();
+	display.shape_lit_solid(&model, transform, color, false, false,
+		false).unwrap();
+	(display, color)
+}
+
+#[test]
+fn light_directional() {
+	// Shining down (+Y) and away from the viewer.
+	let (mut display, color) = lit_frame(Light::Directional {
+		direction: base::Vec3 { x: 0.0, y: 1.0, z: 1.0 },
+		color: [1.0, 1.0, 1.0],
+	}, Transform::IDENTITY, [0.0, 0.0, -1.0]);
+
+	check(&mut display, golden(|x, y| {
+		blinn_phong(color, [0.0, 0.0, -1.0], world(x, y),
+			[0.0, -1.0, -1.0], 1.0)
+	}));
+}
+
+#[test]
+fn light_point() {
+	// 1 in front of the middle, fading out 2 away.
+	let (mut display, color) = lit_frame(Light::Point {
+		position: base::Vec3 { x: 0.0, y: 0.0, z: -1.0 },
+		color: [1.0, 1.0, 1.0],
+		range: 2.0,
+	}, Transform::IDENTITY, [0.0, 0.0, -1.0]);
+
+	check(&mut display, golden(|x, y| {
+		let p = world(x, y);
+		let to = [-p[0], -p[1], -1.0 - p[2]];
+		let fade = 1.0 - dot(to, to).sqrt() / 2.0;
+
+		blinn_phong(color, [0.0, 0.0, -1.0], p, to, fade * fade)
+	}));
+}
+
+#[test]
+fn light_spot() {
+	// 1 in front of the middle, facing it, with a cone that only reaches
+	// the middle 4 pixels (about 20 degrees off, the others are over 38).
+	let (mut display, color) = lit_frame(Light::Spot {
+		position: base::Vec3 { x: 0.0, y: 0.0, z: -1.0 },
+		direction: base::Vec3 { x: 0.0, y: 0.0, z: 1.0 },
+		color: [1.0, 1.0, 1.0],
+		range: 0.0,
+		angle: PI / 6.0,
+	}, Transform::IDENTITY, [0.0, 0.0, -1.0]);
+
+	check(&mut display, golden(|x, y| {
+		let p = world(x, y);
+
+		if (x == 1 || x == 2) && (y == 1 || y == 2) {
+			blinn_phong(color, [0.0, 0.0, -1.0], p,
+				[-p[0], -p[1], -1.0 - p[2]], 1.0)
+		} else {
+			[0.0, 0.0, 0.0, 1.0]
+		}
+	}));
+}
+
+#[test]
+fn light_stretched() {
+	// Stretched to twice as tall, a normal tilted up 45 degrees tilts
+	// half as far: normals transform by the inverse-transpose.
+	let stretch = Transform::IDENTITY.m(base::mat4!(
+		1.0, 0.0, 0.0, 0.0,
+		0.0, 2.0, 0.0, 0.0,
+		0.0, 0.0, 1.0, 0.0,
+		0.0, 0.0, 0.0, 1.0
+	));
+	let (mut display, color) = lit_frame(Light::Directional {
+		direction: base::Vec3 { x: 0.0, y: 0.0, z: 1.0 },
+		color: [1.0, 1.0, 1.0],
+	}, stretch, [0.0, -1.0, -1.0]);
+
+	check(&mut display, golden(|x, y| {
+		blinn_phong(color, [0.0, -0.5, -1.0], world(x, y),
+			[0.0, 0.0, -1.0], 1.0)
+	}));
+}
+
+#[test]
+fn lit_texture() {
+	let (mut display, model) = lit_display();
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+
+	display.shape_lit_texture(&model, Transform::IDENTITY, &texture, tc,
+		false, false, false).unwrap();
+	check(&mut display, golden(|x, y| half(texel(x, y))));
+}
+
+#[test]
+fn lit_tinted() {
+	let (mut display, model) = lit_display();
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+	let tint = [0.5, 1.0, 0.25, 1.0];
+
+	display.shape_lit_tinted(&model, Transform::IDENTITY, &texture, tc,
+		tint, false, false, false).unwrap();
+	check(&mut display, golden(|x, y| half(mul(texel(x, y), tint))));
+}
+
+#[test]
+fn pbr() {
+	let (mut display, model) = lit_display();
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+	let color = [1.0, 0.5, 1.0, 1.0];
+	let mut material = Material::new(color);
+	material.base_color_texture = Some(&texture);
+
+	display.shape_pbr(&model, Transform::IDENTITY, &material, tc, false,
+		false, false).unwrap();
+	check(&mut display, golden(|x, y| half(mul(texel(x, y), color))));
+}
+
+// A square facing the viewer in OBJ's Y-up space, 2 wide, at the top of the
+// frame once it's turned Y-down.
+const OBJ_SQUARE: &str = "v -1 0 0\nv 1 0 0\nv 1 1 0\nv -1 1 0\nf 1 2 3 4\n";
+
+#[test]
+fn import() {
+	let mut display = display();
+	let mesh = Mesh::from_obj(OBJ_SQUARE).unwrap();
+	let handles = mesh.upload(&mut display, |_| None).unwrap();
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	display.shape_solid(&handles.model, Transform::IDENTITY, color, false,
+		false, false);
+	check(&mut display, golden(|_, y| if y < 2 { color } else { CLEAR }));
+}
+
+#[test]
+fn import_camera() {
+	let mut display = display();
+	let mesh = Mesh::from_obj(OBJ_SQUARE).unwrap();
+	let handles = mesh.upload(&mut display, |_| None).unwrap();
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	// Seen from 2 in front with a 90 degree field of view, the square
+	// covers the middle of the top half of the frame.
+	display.set_projection(Projection::Perspective {
+		fov: PI / 2.0, near: 0.5, far: 10.0, reversed_z: false,
+	}).unwrap();
+	display.set_camera(Camera::LookAt {
+		eye: base::Vec3 { x: 0.0, y: 0.0, z: -2.0 },
+		target: base::Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+		up: base::Vec3 { x: 0.0, y: -1.0, z: 0.0 },
+	});
+	display.shape_solid(&handles.model, Transform::IDENTITY, color, false,
+		false, true);
+	check(&mut display, golden(|x, y| {
+		if y == 1 && (x == 1 || x == 2) { color } else { CLEAR }
+	}));
+}
+
+// Looking along z from 2 in front of the origin.
+fn front() -> Camera {
+	Camera::LookAt {
+		eye: base::Vec3 { x: 0.0, y: 0.0, z: -2.0 },
+		target: base::Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+		up: base::Vec3 { x: 0.0, y: -1.0, z: 0.0 },
+	}
+}
+
+// Look from `front()`, seeing from -1 to 1 across, and from 0.5 to 10 away.
+fn ortho_camera(display: &mut Headless) {
+	display.set_projection(Projection::Orthographic {
+		height: 2.0, near: 0.5, far: 10.0, reversed_z: false,
+	}).unwrap();
+	display.set_camera(front());
+}
+
+// A frame with a solid square over its left half at z = 0, casting a shadow
+// onto a lit square behind it at z = 0.5, which doesn't cast.  Returns the
+// pixels of the frame.
+fn shadow_frame(light: Light) -> Vec<u32> {
+	let mut display = display();
+	let caster = display.model(&[
+		-1.0, -1.0, 0.0, 1.0,
+		-1.0, 1.0, 0.0, 1.0,
+		0.0, 1.0, 0.0, 1.0,
+		0.0, -1.0, 0.0, 1.0,
+	], vec![(0, 4)]);
+	let receiver = display.model(&[
+		-1.0, -1.0, 0.5, 1.0,
+		-1.0, 1.0, 0.5, 1.0,
+		1.0, 1.0, 0.5, 1.0,
+		1.0, -1.0, 0.5, 1.0,
+	], vec![(0, 4)]);
+
+	display.set_normals(&receiver, &NORMALS).unwrap();
+	display.light(&[light]).unwrap();
+	display.set_shadows(Some(Shadows { resolution: 64, bias: 0.001,
+		pcf: 0 }));
+	ortho_camera(&mut display);
+	display.shape_solid(&caster, Transform::IDENTITY, CASTER, false,
+		false, true);
+	let shape = display.shape_lit_solid(&receiver, Transform::IDENTITY,
+		[1.0, 1.0, 1.0, 1.0], false, false, true).unwrap();
+	display.shape_shadows(&shape, false, true).unwrap();
+	display.update();
+	display.screenshot().0
+}
+
+const CASTER: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+// Check that the caster covers the left half, that the third column is in
+// its shadow, and that the last column is lit.
+fn check_shadow(pixels: &[u32]) {
+	let black = pack([0.0, 0.0, 0.0, 1.0]);
+
+	for y in 0..WH.1 as usize {
+		let row = &pixels[y * WH.0 as usize..][..WH.0 as usize];
+
+		assert_eq!(row[0], pack(CASTER), "{:08x?}", pixels);
+		assert_eq!(row[1], pack(CASTER), "{:08x?}", pixels);
+		assert_eq!(row[2], black, "{:08x?}", pixels);
+		assert_ne!(row[3], black, "{:08x?}", pixels);
+	}
+}
+
+#[test]
+fn shadow_directional() {
+	// Shining to the right and into the frame, the shadow shifts as far
+	// right as the receiver is behind the caster: past the depth the
+	// shadow map was fitted to.
+	check_shadow(&shadow_frame(Light::Directional {
+		direction: base::Vec3 { x: 1.0, y: 0.0, z: 1.0 },
+		color: [1.0, 1.0, 1.0],
+	}));
+}
+
+#[test]
+fn shadow_spot() {
+	// From the left and in front, the shadow reaches x = 0.5.
+	check_shadow(&shadow_frame(Light::Spot {
+		position: base::Vec3 { x: -1.0, y: 0.0, z: -1.0 },
+		direction: base::Vec3 { x: 1.0, y: 0.0, z: 1.0 },
+		color: [1.0, 1.0, 1.0],
+		range: 0.0,
+		angle: PI / 4.0,
+	}));
+}
+
+#[test]
+fn spot_angle() {
+	let mut display = display();
+	let spot = |angle| Light::Spot {
+		position: base::Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+		direction: base::Vec3 { x: 0.0, y: 0.0, z: 1.0 },
+		color: [1.0, 1.0, 1.0],
+		range: 0.0,
+		angle,
+	};
+
+	assert_eq!(display.light(&[spot(PI / 2.0)]), Err(Error::InvalidLight));
+	assert_eq!(display.light(&[spot(0.0)]), Err(Error::InvalidLight));
+	assert_eq!(display.light(&[spot(PI / 4.0)]), Ok(()));
+}
+
+// A frame split into two viewports, left and right, both looking from
+// `front()`, with a solid square covering them at `z`.
+fn viewports_frame(z: f32) -> (Box<Headless>, [f32; 4]) {
+	let mut display = display();
+	let model = display.model(&[
+		-1.0, -1.0, z, 1.0,
+		-1.0, 1.0, z, 1.0,
+		1.0, 1.0, z, 1.0,
+		1.0, -1.0, z, 1.0,
+	], vec![(0, 4)]);
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	ortho_camera(&mut display);
+	display.set_viewports(&[
+		Viewport::new([0.0, 0.0, 0.5, 1.0], front()),
+		Viewport::new([0.5, 0.0, 0.5, 1.0], front()),
+	]);
+	display.shape_solid(&model, Transform::IDENTITY, color, false, false,
+		true);
+	(display, color)
+}
+
+#[test]
+fn viewports() {
+	let (mut display, color) = viewports_frame(0.0);
+
+	check(&mut display, golden(|_, _| color));
+}
+
+#[test]
+fn viewports_past_far() {
+	// 11 away, past the far plane at 10.
+	let (mut display, _) = viewports_frame(9.0);
+
+	check(&mut display, golden(|_, _| CLEAR));
+}
+
+#[test]
+fn viewports_before_near() {
+	// 0.25 away, before the near plane at 0.5.
+	let (mut display, _) = viewports_frame(-1.75);
+
+	check(&mut display, golden(|_, _| CLEAR));
+}
+
+#[test]
+fn viewports_overlap() {
+	let mut display = display();
+	let far_square = display.model(&SQUARE, vec![(0, 4)]);
+	let near_square = display.model(&[
+		-1.0, -1.0, -1.0, 1.0,
+		-1.0, 1.0, -1.0, 1.0,
+		1.0, 1.0, -1.0, 1.0,
+		1.0, -1.0, -1.0, 1.0,
+	], vec![(0, 4)]);
+	let far = [1.0, 0.5, 0.0, 1.0];
+	let near = [0.0, 1.0, 0.0, 1.0];
+
+	// The later viewport, in the top left, is in front of the near square,
+	// and is drawn over the first even though the first sees the near
+	// square in front of the far one.
+	ortho_camera(&mut display);
+	display.set_viewports(&[
+		Viewport::new([0.0, 0.0, 1.0, 1.0], front()),
+		Viewport::new([0.0, 0.0, 0.5, 0.5], Camera::LookAt {
+			eye: base::Vec3 { x: 0.0, y: 0.0, z: -0.75 },
+			target: base::Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+			up: base::Vec3 { x: 0.0, y: -1.0, z: 0.0 },
+		}),
+	]);
+	display.shape_solid(&far_square, Transform::IDENTITY, far, false,
+		false, true);
+	display.shape_solid(&near_square, Transform::IDENTITY, near, false,
+		false, true);
+	check(&mut display, golden(|x, y| {
+		if x < 2 && y < 2 { far } else { near }
+	}));
+}
+
+const FOG: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+
+// A solid square covering the frame, with `fog` in `FOG`'s color.  Seen from
+// `front()` through a projection that leaves its clip space z at 0, so the
+// fog distance at each of its corners, and across it, is the square root of
+// 2.
+fn fog_frame(fog: FogMode, height: Option<(f32, f32)>)
+	-> (Box<Headless>, [f32; 4])
+{
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let color = [1.0, 0.0, 0.0, 1.0];
+
+	display.set_projection(Projection::Orthographic {
+		height: 2.0, near: 1.0, far: 3.0, reversed_z: false,
+	}).unwrap();
+	display.set_camera(front());
+	display.set_fog(Some(Fog {
+		mode: fog, color: Some((FOG[0], FOG[1], FOG[2])), height,
+	}));
+	display.shape_solid(&model, Transform::IDENTITY, color, false, true,
+		true);
+	(display, color)
+}
+
+fn fogged(color: [f32; 4], amount: f32) -> [f32; 4] {
+	let mix = |i: usize| color[i] * (1.0 - amount) + FOG[i] * amount;
+
+	[mix(0), mix(1), mix(2), mix(3)]
+}
+
+#[test]
+fn fog_modes() {
+	let z = 2.0f32.sqrt();
+	let modes = [
+		(FogMode::Cubic { start: 0.5, range: 2.0 },
+			((z - 0.5) / 2.0).powi(3)),
+		(FogMode::Linear { start: 0.5, range: 2.0 }, (z - 0.5) / 2.0),
+		(FogMode::Exponential { density: 0.5 }, 1.0 - (-0.5 * z).exp()),
+		(FogMode::ExponentialSquared { density: 0.5 },
+			1.0 - (-(0.5 * z) * (0.5 * z)).exp()),
+	];
+
+	for &(mode, amount) in modes.iter() {
+		let (mut display, color) = fog_frame(mode, None);
+
+		check(&mut display, golden(|_, _| fogged(color, amount)));
+	}
+}
+
+#[test]
+fn fog_height() {
+	// Thinning out above 0 (up is -Y), by e^(-height).
+	let (mut display, color) = fog_frame(FogMode::Linear {
+		start: 0.0, range: 2.0,
+	}, Some((0.0, 1.0)));
+
+	check(&mut display, golden(|x, y| {
+		let height = -world(x, y)[1];
+		let amount = 2.0f32.sqrt() / 2.0 * (-height.max(0.0)).exp();
+
+		fogged(color, amount)
+	}));
+}
+
+#[test]
+fn fog_sky() {
+	// Redder around the horizon, going around from behind.
+	let sky = VFrame((0..16).map(|i| 0xFF00_0000 | (i * 16)).collect());
+	let (mut display, _) = fog_frame(FogMode::Linear {
+		start: 0.0, range: 1.0,
+	}, None);
+
+	// Thick enough to hide the square, in the sky's color.  The square is
+	// 2 in front, so its pixels are this much around the horizon,
+	// between neighboring colors.
+	display.set_fog_sky(Some(((16, 1), &sky)));
+	check(&mut display, golden(|x, y| {
+		let around = world(x, y)[0].atan2(2.0) / (2.0 * PI) + 0.5;
+
+		[(around * 16.0 - 0.5) * 16.0 / 255.0, 0.0, 0.0, 1.0]
+	}));
+}
+
+// A model of a square, 2 across, at `z`.
+fn square(display: &mut Headless, z: f32) -> base::Model {
+	display.model(&[
+		-1.0, -1.0, z, 1.0,
+		-1.0, 1.0, z, 1.0,
+		1.0, 1.0, z, 1.0,
+		1.0, -1.0, z, 1.0,
+	], vec![(0, 4)])
+}
+
+#[test]
+fn projection_invalid() {
+	let mut display = display();
+	let perspective = |fov, near, far| Projection::Perspective {
+		fov, near, far, reversed_z: false,
+	};
+	let orthographic = |height, near, far| Projection::Orthographic {
+		height, near, far, reversed_z: false,
+	};
+
+	for projection in [
+		perspective(PI / 2.0, 0.0, 10.0),
+		perspective(PI / 2.0, 2.0, 1.0),
+		perspective(PI, 0.5, 10.0),
+		perspective(::std::f32::NAN, 0.5, 10.0),
+		orthographic(0.0, 0.5, 10.0),
+		orthographic(2.0, 1.0, 1.0),
+	].iter() {
+		assert_eq!(display.set_projection(*projection),
+			Err(Error::InvalidProjection), "{:?}", projection);
+	}
+	assert_eq!(display.set_projection(orthographic(2.0, 0.5, 10.0)),
+		Ok(()));
+}
+
+#[test]
+fn projection_fov() {
+	let mut display = display();
+	let model = square(&mut display, 0.0);
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	// Half as wide as 90 degrees, so the square 2 away fills the frame.
+	display.set_projection(Projection::Perspective {
+		fov: 2.0 * 0.5f32.atan(), near: 0.5, far: 10.0,
+		reversed_z: false,
+	}).unwrap();
+	display.set_camera(front());
+	display.shape_solid(&model, Transform::IDENTITY, color, false, false,
+		true);
+	check(&mut display, golden(|_, _| color));
+}
+
+#[test]
+fn projection_orthographic() {
+	let mut display = display();
+	let model = square(&mut display, 5.0);
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	// 4 tall, so the square covers the middle no matter how far away.
+	display.set_projection(Projection::Orthographic {
+		height: 4.0, near: 0.5, far: 10.0, reversed_z: false,
+	}).unwrap();
+	display.set_camera(front());
+	display.shape_solid(&model, Transform::IDENTITY, color, false, false,
+		true);
+	check(&mut display, golden(|x, y| {
+		if (x == 1 || x == 2) && (y == 1 || y == 2) {
+			color
+		} else {
+			CLEAR
+		}
+	}));
+}
+
+#[test]
+fn projection_reversed_z() {
+	let mut display = display();
+	let near_square = square(&mut display, -1.0);
+	let far_square = square(&mut display, 0.0);
+	let near = [0.0, 1.0, 0.0, 1.0];
+	let far = [1.0, 0.5, 0.0, 1.0];
+
+	// The near square still hides the far one drawn after it.
+	display.set_projection(Projection::Orthographic {
+		height: 2.0, near: 0.5, far: 10.0, reversed_z: true,
+	}).unwrap();
+	display.set_camera(front());
+	display.shape_solid(&near_square, Transform::IDENTITY, near, false,
+		false, true);
+	display.shape_solid(&far_square, Transform::IDENTITY, far, false,
+		false, true);
+	check(&mut display, golden(|_, _| near));
+}
+
+// Each kind of camera, turned half a turn around z from `front()`, sees a
+// square over the left half of the world on the right half of the frame.
+#[test]
+fn camera_kinds() {
+	let eye = base::Vec3 { x: 0.0, y: 0.0, z: -2.0 };
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	for camera in [
+		Camera::LookAt {
+			eye,
+			target: base::Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+			up: base::Vec3 { x: 0.0, y: 1.0, z: 0.0 },
+		},
+		Camera::Quaternion { eye, orientation: [0.0, 0.0, 1.0, 0.0] },
+		// Written column by column.
+		Camera::View(Transform(base::mat4!(
+			-1.0, 0.0, 0.0, 0.0,
+			0.0, -1.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 2.0, 1.0
+		))),
+	].iter() {
+		let mut display = display();
+		let model = display.model(&[
+			-1.0, -1.0, 0.0, 1.0,
+			-1.0, 1.0, 0.0, 1.0,
+			0.0, 1.0, 0.0, 1.0,
+			0.0, -1.0, 0.0, 1.0,
+		], vec![(0, 4)]);
+
+		display.set_projection(Projection::Orthographic {
+			height: 2.0, near: 0.5, far: 10.0, reversed_z: false,
+		}).unwrap();
+		display.set_camera(*camera);
+		display.shape_solid(&model, Transform::IDENTITY, color, false,
+			false, true);
+		check(&mut display, golden(|x, _| {
+			if x >= 2 { color } else { CLEAR }
+		}));
+	}
+}
+
+// A frame showing a render target, turned half a turn around z from
+// `front()`, over the whole of it, with a square over the left half of the
+// world.  Returns the display, the render target, the square and its color.
+fn render_target_frame() -> (Box<Headless>, base::Texture, base::Shape,
+	[f32; 4])
+{
+	let mut display = display();
+	let half = display.model(&[
+		-1.0, -1.0, 0.0, 1.0,
+		-1.0, 1.0, 0.0, 1.0,
+		0.0, 1.0, 0.0, 1.0,
+		0.0, -1.0, 0.0, 1.0,
+	], vec![(0, 4)]);
+	let screen = display.model(&SQUARE, vec![(0, 4)]);
+	let tc = display.texcoords(&TEXCOORDS);
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	ortho_camera(&mut display);
+	let target = display.create_render_target(WH, Camera::Quaternion {
+		eye: base::Vec3 { x: 0.0, y: 0.0, z: -2.0 },
+		orientation: [0.0, 0.0, 1.0, 0.0],
+	});
+	let shape = display.shape_solid(&half, Transform::IDENTITY, color,
+		false, false, true);
+	display.shape_texture(&screen, Transform::IDENTITY, &target, tc,
+		false, false, false);
+	(display, target, shape, color)
+}
+
+#[test]
+fn render_target() {
+	let (mut display, _, _, color) = render_target_frame();
+
+	check(&mut display, golden(|x, _| if x >= 2 { color } else { CLEAR }));
+}
+
+#[test]
+fn render_target_camera() {
+	let (mut display, target, _, color) = render_target_frame();
+
+	display.set_render_target_camera(&target, front()).unwrap();
+	check(&mut display, golden(|x, _| if x < 2 { color } else { CLEAR }));
+}
+
+#[test]
+fn render_target_shapes() {
+	let (mut display, target, shape, color) = render_target_frame();
+
+	display.set_render_target_shapes(&target, Some(&[&shape])).unwrap();
+	check(&mut display, golden(|x, _| if x >= 2 { color } else { CLEAR }));
+	display.set_render_target_shapes(&target, Some(&[])).unwrap();
+	check(&mut display, golden(|_, _| CLEAR));
+}
+
+#[test]
+fn post_process() {
+	let mut display = display();
+	let world = display.model(&SQUARE, vec![(0, 4)]);
+	let gui = display.model(&[
+		-1.0, -1.0, 0.0, 1.0,
+		-1.0, 1.0, 0.0, 1.0,
+		0.0, 1.0, 0.0, 1.0,
+		0.0, -1.0, 0.0, 1.0,
+	], vec![(0, 4)]);
+	let gray = [0.5, 0.5, 0.5, 1.0];
+	let color = [1.0, 0.5, 0.0, 1.0];
+
+	// The passes run in order over the world, but not the GUI over its
+	// left half.
+	ortho_camera(&mut display);
+	display.set_post_process(&[
+		PostProcess::Vignette { strength: 1.0 },
+		PostProcess::ToneMap { exposure: 1.0, gamma: 1.0 },
+	]);
+	display.shape_solid(&world, Transform::IDENTITY, gray, false, false,
+		true);
+	display.shape_solid(&gui, Transform::IDENTITY, color, false, false,
+		false);
+	check(&mut display, golden(|x, y| {
+		if x < 2 {
+			return color;
+		}
+
+		let d = |i: usize| (i as f32 + 0.5) / 2.0 - 1.0;
+		let c = 0.5 * (1.0 - (d(x) * d(x) + d(y) * d(y)) / 2.0);
+		let c = c / (1.0 + c);
+
+		[c, c, c, 1.0]
+	}));
+
+	display.set_post_process(&[]);
+	check(&mut display, golden(|x, _| if x < 2 { color } else { gray }));
+}
+
+#[test]
+fn drop_shape() {
+	let mut display = display();
+	let near_square = square(&mut display, -1.0);
+	let far_square = square(&mut display, 0.0);
+	let near = [0.0, 1.0, 0.0, 1.0];
+	let far = [1.0, 0.5, 0.0, 1.0];
+
+	ortho_camera(&mut display);
+	let shape = display.shape_solid(&near_square, Transform::IDENTITY,
+		near, false, false, true);
+	display.shape_solid(&far_square, Transform::IDENTITY, far, false,
+		false, true);
+	check(&mut display, golden(|_, _| near));
+	display.drop_shape(&shape);
+	check(&mut display, golden(|_, _| far));
+	assert_eq!(display.try_drop_shape(&shape), Err(Error::DoubleDrop));
+}
+
+#[test]
+fn alpha_order() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let near = [1.0, 0.0, 0.0, 0.5];
+	let far = [0.0, 1.0, 0.0, 0.5];
+	let blend = |color: [f32; 4], under: [f32; 4]| {
+		let mix = |i: usize| color[i] * color[3]
+			+ under[i] * (1.0 - color[3]);
+
+		[mix(0), mix(1), mix(2), color[3] * color[3]
+			+ under[3] * (1.0 - color[3])]
+	};
+
+	// Made nearest first, but blended farthest first.
+	ortho_camera(&mut display);
+	display.shape_solid(&model, Transform::IDENTITY.t(base::Vec3 {
+		x: 0.0, y: 0.0, z: -1.0,
+	}), near, true, false, true);
+	display.shape_solid(&model, Transform::IDENTITY, far, true, false,
+		true);
+	check(&mut display, golden(|_, _| blend(near, blend(far, CLEAR))));
+}
+
+#[test]
+fn gui_layers() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let first = [1.0, 0.5, 0.0, 1.0];
+	let second = [0.0, 1.0, 0.0, 1.0];
+	let a = display.shape_solid(&model, Transform::IDENTITY, first, false,
+		false, false);
+	let b = display.shape_solid(&model, Transform::IDENTITY, second, false,
+		false, false);
+
+	check(&mut display, golden(|_, _| second));
+	display.gui_layer(&a, 1).unwrap();
+	check(&mut display, golden(|_, _| first));
+	// Back on the same layer, it's drawn in the order it was made again.
+	display.gui_layer(&a, 0).unwrap();
+	check(&mut display, golden(|_, _| second));
+	display.drop_shape(&b);
+	check(&mut display, golden(|_, _| first));
+}
+
+#[test]
+fn drop_resources() {
+	let mut display = display();
+	let model = display.model(&SQUARE, vec![(0, 4)]);
+	let texture = texture(&mut display);
+	let tc = display.texcoords(&TEXCOORDS);
+	let gradient = display.gradient(&[1.0; 16]);
+	let (stale_model, stale_texture) = (base::Model(model.0),
+		base::Texture(texture.0, texture.1, texture.2));
+
+	// Shapes already made keep drawing.
+	display.shape_texture(&model, Transform::IDENTITY, &texture, tc,
+		false, false, false);
+	display.drop_model(model).unwrap();
+	display.drop_texture(texture).unwrap();
+	display.drop_gradient(gradient).unwrap();
+	check(&mut display, golden(texel));
+
+	let tc = display.texcoords(&TEXCOORDS);
+	assert_eq!(display.drop_texcoords(tc), Ok(()));
+
+	// Handles to them are stale, even once their slots are reused.
+	display.model(&SQUARE, vec![(0, 4)]);
+	assert_eq!(display.try_shape_solid(&stale_model, Transform::IDENTITY,
+		[1.0; 4], false, false, false).err(), Some(Error::StaleHandle));
+	assert_eq!(display.drop_model(stale_model), Err(Error::StaleHandle));
+	assert_eq!(display.drop_texture(stale_texture),
+		Err(Error::StaleHandle));
+}